@@ -0,0 +1,132 @@
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use num::Signed;
+
+use crate::matrix::{Allocator, Matrix};
+
+/* A point or displacement in the plane. When indexing a Matrix, y selects
+ * the row and x the column, so y grows downwards as in a rendered grid. */
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point2<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T: Signed + PartialOrd + Copy> Point2<T> {
+    /* L1 norm: steps needed moving only horizontally or vertically */
+    pub fn manhattan(&self) -> T {
+        self.x.abs() + self.y.abs()
+    }
+
+    /* L-infinity norm: steps needed when diagonal moves are allowed */
+    pub fn chebyshev(&self) -> T {
+        let (x, y) = (self.x.abs(), self.y.abs());
+        if x > y { x } else { y }
+    }
+
+    /* Component-wise, giving a single king's move in the same direction */
+    pub fn signum(&self) -> Self {
+        Point2 { x: self.x.signum(), y: self.y.signum() }
+    }
+}
+
+impl<T: TryInto<usize> + Copy> Point2<T> {
+    /* Matrix (row, column), or None if either coordinate is negative */
+    pub fn to_index(&self) -> Option<(usize, usize)> {
+        Some((self.y.try_into().ok()?, self.x.try_into().ok()?))
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T> From<Point2<T>> for (T, T) {
+    fn from(p: Point2<T>) -> Self {
+        (p.x, p.y)
+    }
+}
+
+impl<T: Add<Output=T>> Add for Point2<T> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Point2 { x: self.x + rhs.x, y: self.y + rhs.y }
+    }
+}
+
+impl<T: Sub<Output=T>> Sub for Point2<T> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Point2 { x: self.x - rhs.x, y: self.y - rhs.y }
+    }
+}
+
+impl<T: Neg<Output=T>> Neg for Point2<T> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Point2 { x: -self.x, y: -self.y }
+    }
+}
+
+impl<T: Mul<Output=T> + Copy> Mul<T> for Point2<T> {
+    type Output = Self;
+    fn mul(self, k: T) -> Self {
+        Point2 { x: self.x * k, y: self.y * k }
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+fn checked_index<T, U, A>(g: &Matrix<U, A>, p: Point2<T>) -> (usize, usize)
+    where T: TryInto<usize> + Copy + Debug,
+          A: Allocator
+{
+    match p.to_index() {
+        Some(idx) if g.in_bounds(idx) => idx,
+        _ => {
+            let (m, n) = g.get_dims();
+            panic!("point {:?} out of bounds for {}x{} matrix", p, m, n);
+        }
+    }
+}
+
+impl<T, U, A> Index<Point2<T>> for Matrix<U, A>
+    where T: TryInto<usize> + Copy + Debug,
+          A: Allocator
+{
+    type Output = U;
+    fn index(&self, p: Point2<T>) -> &Self::Output {
+        &self[checked_index(self, p)]
+    }
+}
+
+impl<T, U, A> IndexMut<Point2<T>> for Matrix<U, A>
+    where T: TryInto<usize> + Copy + Debug,
+          A: Allocator
+{
+    fn index_mut(&mut self, p: Point2<T>) -> &mut Self::Output {
+        let idx = checked_index(self, p);
+        &mut self[idx]
+    }
+}
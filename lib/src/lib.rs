@@ -1,8 +1,11 @@
-#![feature(allocator_api)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 use std::alloc::System;
 
+pub mod geom;
 pub mod parser;
+pub mod region;
 pub mod matrix;
+pub mod sparse;
 pub mod tree;
 
 #[global_allocator]
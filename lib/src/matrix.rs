@@ -1,62 +1,456 @@
-use std::alloc::{Allocator, System, Layout};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{StepBy, Take};
 use std::ops::{Index, IndexMut};
-use std::ptr::NonNull;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ptr::{self, NonNull};
+use std::slice;
 
-#[derive(Clone)]
-pub struct Matrix<T> {
+mod arith;
+mod bits;
+mod orient;
+mod par;
+mod prefix;
+mod ray;
+mod render;
+mod reshape;
+mod serial;
+mod storage;
+#[cfg(test)]
+mod testing;
+mod view;
+pub use bits::BitMatrix;
+pub use prefix::SummedArea;
+pub use ray::{Direction, Ray, TakeUntilBlocking, TakeUntilBlockingExt};
+pub use serial::FixedWidth;
+pub use storage::{Allocator, Global};
+pub use view::{MatrixView, MatrixViewMut, Rect};
+
+/* A single row or column, walked through storage with a stride */
+pub type Line<'a, T> = Take<StepBy<slice::Iter<'a, T>>>;
+
+/* (row, column) offsets to the adjacent cells */
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+pub const ADJACENT: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1),
+                                           ( 0, -1),          ( 0, 1),
+                                           ( 1, -1), ( 1, 0), ( 1, 1)];
+
+/* Storage order. Indices are always (row, column) whatever the order. */
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Order {
+    #[default]
+    ColMajor,
+    RowMajor,
+}
+
+impl Order {
+    pub fn flipped(self) -> Self {
+        match self {
+            Order::ColMajor => Order::RowMajor,
+            Order::RowMajor => Order::ColMajor,
+        }
+    }
+}
+
+pub struct Matrix<T, A: Allocator = Global> {
     dims: (usize, usize),
-    layout: Layout,
+    order: Order,
     base_ptr: NonNull<T>,
+    alloc: A,
 }
 
-impl<T: Copy> Matrix<T> {
-    pub fn new(m: usize, n: usize) -> Self {
-        let layout = Layout::array::<T>(m*n).unwrap();
-        Matrix { dims: (m, n),
-                 layout: layout,
-                 base_ptr: System.allocate(layout)
-                                 .unwrap().cast::<T>() }
+impl<T, A: Allocator> Matrix<MaybeUninit<T>, A> {
+    /// # Safety
+    /// Every element must have been written before calling this.
+    pub unsafe fn assume_init(self) -> Matrix<T, A> {
+        let me = ManuallyDrop::new(self);
+        Matrix { dims: me.dims,
+                 order: me.order,
+                 base_ptr: me.base_ptr.cast::<T>(),
+                 alloc: ptr::read(&me.alloc) }
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn new_filled(m: usize, n: usize, e: T) -> Self {
+        Self::new_filled_ordered(m, n, Order::default(), e)
+    }
+
+    pub fn new_filled_ordered(m: usize, n: usize, order: Order, e: T) -> Self {
+        Self::from_fn_ordered(m, n, order, |_, _| e.clone())
+    }
+}
+
+impl<T: Clone, A: Allocator> Matrix<T, A> {
+    pub fn fill(&mut self, e: T) {
+        for x in self.as_mut_slice() {
+            *x = e.clone();
+        }
+    }
+}
+
+impl<T> Matrix<T> {
+    /* The only way to get at uninitialised memory */
+    pub fn new_uninit(m: usize, n: usize) -> Matrix<MaybeUninit<T>> {
+        Self::new_uninit_ordered(m, n, Order::default())
+    }
+
+    pub fn new_uninit_ordered(m: usize, n: usize, order: Order) -> Matrix<MaybeUninit<T>> {
+        Self::new_uninit_in(m, n, order, Global)
+    }
+
+    pub fn from_fn<F>(m: usize, n: usize, f: F) -> Self
+        where F: FnMut(usize, usize) -> T
+    {
+        Self::from_fn_ordered(m, n, Order::default(), f)
+    }
+
+    pub fn from_fn_ordered<F>(m: usize, n: usize, order: Order, f: F) -> Self
+        where F: FnMut(usize, usize) -> T
+    {
+        Self::from_fn_in(m, n, order, Global, f)
+    }
+
+    /* Elements of v are taken in row-major (reading) order */
+    pub fn from_vec(m: usize, n: usize, v: Vec<T>) -> Self {
+        Self::from_vec_ordered(m, n, Order::default(), v)
+    }
+
+    /* As from_vec, stored in the given order. v is still read row by row,
+     * so with Order::RowMajor it is copied over in one go. */
+    pub fn from_vec_ordered(m: usize, n: usize, order: Order, mut v: Vec<T>) -> Self {
+        assert_eq!(m.checked_mul(n), Some(v.len()),
+                   "vector of length {} does not fill a {}x{} matrix", v.len(), m, n);
+        let mut u = Self::new_uninit_ordered(m, n, order);
+        match order {
+            Order::RowMajor => unsafe {
+                /* The elements now belong to u, so v must not drop them */
+                let dst = u.as_mut_slice().as_mut_ptr().cast::<T>();
+                ptr::copy_nonoverlapping(v.as_ptr(), dst, v.len());
+                v.set_len(0);
+            },
+            Order::ColMajor => {
+                for (k, e) in v.into_iter().enumerate() {
+                    u[(k / n, k % n)].write(e);
+                }
+            }
+        }
+        unsafe { u.assume_init() }
+    }
+
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn new_in(m: usize, n: usize, alloc: A) -> Self
+        where T: Default
+    {
+        Self::from_fn_in(m, n, Order::default(), alloc, |_, _| T::default())
+    }
+
+    pub fn new_uninit_in(m: usize, n: usize, order: Order, alloc: A)
+        -> Matrix<MaybeUninit<T>, A>
+    {
+        /* Every other use of m*n relies on this check */
+        let len = m.checked_mul(n).expect("matrix dimensions overflow");
+        let base_ptr = storage::allocate::<MaybeUninit<T>, A>(len, &alloc);
+        Matrix { dims: (m, n), order, base_ptr, alloc }
+    }
+
+    /* f is called in storage order */
+    pub fn from_fn_in<F>(m: usize, n: usize, order: Order, alloc: A, mut f: F) -> Self
+        where F: FnMut(usize, usize) -> T
+    {
+        /* A panic in f leaks the elements written so far, but never
+         * drops uninitialised ones */
+        let mut u = Self::new_uninit_in(m, n, order, alloc);
+        for k in 0..u.len() {
+            let (i, j) = u.index_of(k);
+            u.as_mut_slice()[k].write(f(i, j));
+        }
+        unsafe { u.assume_init() }
+    }
+
+    /* Forget that the elements are initialised, without dropping them */
+    fn into_uninit(self) -> Matrix<MaybeUninit<T>, A> {
+        let me = ManuallyDrop::new(self);
+        Matrix { dims: me.dims,
+                 order: me.order,
+                 base_ptr: me.base_ptr.cast::<MaybeUninit<T>>(),
+                 alloc: unsafe { ptr::read(&me.alloc) } }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
     }
 
     pub fn get_dims(&self) -> (usize, usize) {
         self.dims
     }
 
-    pub fn fill(&mut self, e: T) {
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    pub fn in_bounds(&self, (i, j): (usize, usize)) -> bool {
+        let (m, n) = self.dims;
+        i < m && j < n
+    }
+
+    pub fn get(&self, index: (usize, usize)) -> Option<&T> {
+        if self.in_bounds(index) {
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: (usize, usize)) -> Option<&mut T> {
+        if self.in_bounds(index) {
+            Some(unsafe { self.get_unchecked_mut(index) })
+        } else {
+            None
+        }
+    }
+
+    /// # Safety
+    /// Caller must guarantee that `index` is in bounds.
+    pub unsafe fn get_unchecked(&self, index: (usize, usize)) -> &T {
+        &*self.base_ptr.as_ptr().add(self.offset(index))
+    }
+
+    /// # Safety
+    /// Caller must guarantee that `index` is in bounds.
+    pub unsafe fn get_unchecked_mut(&mut self, index: (usize, usize)) -> &mut T {
+        &mut *self.base_ptr.as_ptr().add(self.offset(index))
+    }
+
+    /* Storage order */
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item=((usize, usize), &T)> {
+        self.iter().enumerate().map(|(k, e)| (self.index_of(k), e))
+    }
+
+    pub fn row(&self, i: usize) -> Line<'_, T> {
         let (m, n) = self.dims;
+        let (si, sj) = self.strides();
+        assert!(i < m, "row {} out of bounds for {}x{} matrix", i, m, n);
+        self.line(i*si, sj, n)
+    }
+
+    pub fn col(&self, j: usize) -> Line<'_, T> {
+        let (m, n) = self.dims;
+        let (si, sj) = self.strides();
+        assert!(j < n, "column {} out of bounds for {}x{} matrix", j, m, n);
+        self.line(j*sj, si, m)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item=Line<'_, T>> {
+        let (m, _) = self.dims;
+        (0..m).map(|i| self.row(i))
+    }
+
+    pub fn cols(&self) -> impl Iterator<Item=Line<'_, T>> {
+        let (_, n) = self.dims;
+        (0..n).map(|j| self.col(j))
+    }
+
+    /* Distance in storage between neighbouring rows and columns */
+    fn strides(&self) -> (usize, usize) {
+        let (m, n) = self.dims;
+        match self.order {
+            Order::ColMajor => (1, m),
+            Order::RowMajor => (n, 1),
+        }
+    }
+
+    fn offset(&self, (i, j): (usize, usize)) -> usize {
+        let (si, sj) = self.strides();
+        i*si + j*sj
+    }
+
+    fn index_of(&self, k: usize) -> (usize, usize) {
+        index_of(self.dims, self.order, k)
+    }
+
+    fn line(&self, start: usize, stride: usize, len: usize) -> Line<'_, T> {
+        let s = self.as_slice();
+        s[start.min(s.len())..].iter().step_by(stride).take(len)
+    }
+
+    /* Cannot overflow, as new_uninit_in checked it */
+    fn len(&self) -> usize {
+        let (m, n) = self.dims;
+        m*n
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.base_ptr.as_ptr(), self.len()) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.base_ptr.as_ptr(), self.len()) }
+    }
+
+    fn check_bounds(&self, index: (usize, usize)) {
+        if !self.in_bounds(index) {
+            let (m, n) = self.dims;
+            panic!("index {:?} out of bounds for {}x{} matrix", index, m, n);
+        }
+    }
+}
+
+/* Neighbourhoods. These only capture the dimensions, so the matrix may be
+ * mutated while walking them. */
+impl<T, A: Allocator> Matrix<T, A> {
+    /* Move from idx by delta, or None if that leaves the matrix */
+    pub fn step(&self, idx: (usize, usize), delta: (isize, isize)) -> Option<(usize, usize)> {
+        step(self.dims, idx, delta)
+    }
+
+    /* Move from idx by delta, wrapping around the edges */
+    pub fn step_wrapping(&self, idx: (usize, usize), delta: (isize, isize)) -> (usize, usize) {
+        step_wrapping(self.dims, idx, delta)
+    }
+
+    pub fn neighbours4(&self, idx: (usize, usize)) -> impl Iterator<Item=(usize, usize)> {
+        let dims = self.dims;
+        ORTHOGONAL.into_iter().filter_map(move |d| step(dims, idx, d))
+    }
+
+    pub fn neighbours8(&self, idx: (usize, usize)) -> impl Iterator<Item=(usize, usize)> {
+        let dims = self.dims;
+        ADJACENT.into_iter().filter_map(move |d| step(dims, idx, d))
+    }
+
+    /* Toroidal variants. On matrices narrower than three cells the same
+     * neighbour can be reached more than once. */
+    pub fn neighbours4_wrapping(&self, idx: (usize, usize)) -> impl Iterator<Item=(usize, usize)> {
+        let dims = self.dims;
+        ORTHOGONAL.into_iter().map(move |d| step_wrapping(dims, idx, d))
+    }
+
+    pub fn neighbours8_wrapping(&self, idx: (usize, usize)) -> impl Iterator<Item=(usize, usize)> {
+        let dims = self.dims;
+        ADJACENT.into_iter().map(move |d| step_wrapping(dims, idx, d))
+    }
+}
+
+fn step((m, n): (usize, usize), (i, j): (usize, usize), (di, dj): (isize, isize))
+    -> Option<(usize, usize)>
+{
+    let i = i.checked_add_signed(di).filter(|&i| i < m)?;
+    let j = j.checked_add_signed(dj).filter(|&j| j < n)?;
+    Some((i, j))
+}
+
+fn step_wrapping((m, n): (usize, usize), (i, j): (usize, usize), (di, dj): (isize, isize))
+    -> (usize, usize)
+{
+    let wrap = |x: usize, dx: isize, len: usize| {
+        (x as isize + dx).rem_euclid(len as isize) as usize
+    };
+    (wrap(i, di, m), wrap(j, dj, n))
+}
+
+/* (row, column) of the k-th element in storage */
+fn index_of((m, n): (usize, usize), order: Order, k: usize) -> (usize, usize) {
+    match order {
+        Order::ColMajor => (k % m, k / m),
+        Order::RowMajor => (k / n, k % n),
+    }
+}
+
+/* Element-wise, so that the copy owns its own allocation */
+impl<T: Clone, A: Allocator + Clone> Clone for Matrix<T, A> {
+    fn clone(&self) -> Self {
+        let (m, n) = self.dims;
+        Self::from_fn_in(m, n, self.order, self.alloc.clone(), |i, j| self[(i, j)].clone())
+    }
+}
+
+/* Equality and hashing look through the storage order and allocator */
+impl<T: PartialEq, A: Allocator, B: Allocator> PartialEq<Matrix<T, B>> for Matrix<T, A> {
+    fn eq(&self, other: &Matrix<T, B>) -> bool {
+        if self.dims != other.dims {
+            return false;
+        }
+        if self.order == other.order {
+            return self.as_slice() == other.as_slice();
+        }
+        self.indexed_iter().all(|(idx, e)| *e == other[idx])
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for Matrix<T, A> {}
+
+impl<T: Hash, A: Allocator> Hash for Matrix<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dims.hash(state);
+        for row in self.rows() {
+            for e in row {
+                e.hash(state);
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Matrix<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_grid(f, "Matrix", self.dims, |idx| &self[idx])
+    }
+}
+
+/* One row per line */
+fn debug_grid<'a, T, F>(f: &mut fmt::Formatter<'_>, name: &str, (m, n): (usize, usize), at: F)
+    -> fmt::Result
+    where T: fmt::Debug + 'a,
+          F: Fn((usize, usize)) -> &'a T
+{
+    writeln!(f, "{} {}x{} [", name, m, n)?;
+    for i in 0..m {
+        write!(f, "    [")?;
         for j in 0..n {
-            for i in 0..m {
-                self[(i, j)] = e;
+            if j > 0 {
+                write!(f, ", ")?;
             }
+            write!(f, "{:?}", at((i, j)))?;
         }
+        writeln!(f, "],")?;
     }
+    write!(f, "]")
 }
 
-impl<T> Drop for Matrix<T> {
+impl<T, A: Allocator> Drop for Matrix<T, A> {
     fn drop(&mut self) {
         unsafe {
-            System.deallocate(self.base_ptr.cast::<u8>(), self.layout);
+            ptr::drop_in_place(self.as_mut_slice());
+            storage::deallocate(self.base_ptr, self.len(), &self.alloc);
         }
     }
 }
 
-impl<T> Index<(usize, usize)> for Matrix<T> {
+/* The matrix owns its elements outright, as a Vec<T> would */
+unsafe impl<T: Send, A: Allocator + Send> Send for Matrix<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for Matrix<T, A> {}
+
+impl<T, A: Allocator> Index<(usize, usize)> for Matrix<T, A> {
     type Output = T;
     fn index(&self, index: (usize, usize)) -> &Self::Output {
-        let (m, _) = self.dims;
-        let (i, j) = index;
-        unsafe {
-            self.base_ptr.as_ptr().add(i + j*m).as_ref().unwrap()
-        }
+        self.check_bounds(index);
+        unsafe { self.get_unchecked(index) }
     }
 }
 
-impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+impl<T, A: Allocator> IndexMut<(usize, usize)> for Matrix<T, A> {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
-        let (m, _) = self.dims;
-        let (i, j) = index;
-        unsafe {
-            self.base_ptr.as_ptr().add(i + j*m).as_mut().unwrap()
-        }
+        self.check_bounds(index);
+        unsafe { self.get_unchecked_mut(index) }
     }
 }
@@ -0,0 +1,113 @@
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+use num::Num;
+
+use super::{Allocator, Matrix};
+
+/* Element-wise transforms. Results keep the storage order and allocator of
+ * self, and closures are called in storage order. */
+impl<T, A: Allocator + Clone> Matrix<T, A> {
+    pub fn map<U, F>(&self, mut f: F) -> Matrix<U, A>
+        where F: FnMut(&T) -> U
+    {
+        let (m, n) = self.dims;
+        Matrix::from_fn_in(m, n, self.order, self.alloc.clone(), |i, j| f(&self[(i, j)]))
+    }
+
+    pub fn zip_with<U, V, B, F>(&self, other: &Matrix<U, B>, mut f: F) -> Matrix<V, A>
+        where B: Allocator,
+              F: FnMut(&T, &U) -> V
+    {
+        self.check_same_dims(other.dims);
+        let (m, n) = self.dims;
+        Matrix::from_fn_in(m, n, self.order, self.alloc.clone(),
+                           |i, j| f(&self[(i, j)], &other[(i, j)]))
+    }
+
+    /* Hadamard product */
+    pub fn mul_elementwise(&self, other: &Self) -> Self
+        where T: Num + Clone
+    {
+        self.zip_with(other, |a, b| a.clone() * b.clone())
+    }
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+        where F: FnMut(B, &T) -> B
+    {
+        self.iter().fold(init, f)
+    }
+
+    fn check_same_dims(&self, dims: (usize, usize)) {
+        if self.dims != dims {
+            let ((m, n), (p, q)) = (self.dims, dims);
+            panic!("mismatched dimensions {}x{} and {}x{}", m, n, p, q);
+        }
+    }
+}
+
+impl<T: Num + Clone, A: Allocator + Clone> Add for &Matrix<T, A> {
+    type Output = Matrix<T, A>;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a.clone() + b.clone())
+    }
+}
+
+impl<T: Num + Clone, A: Allocator + Clone> Sub for &Matrix<T, A> {
+    type Output = Matrix<T, A>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a.clone() - b.clone())
+    }
+}
+
+impl<T: Num + Clone, A: Allocator> AddAssign<&Matrix<T, A>> for Matrix<T, A> {
+    fn add_assign(&mut self, rhs: &Matrix<T, A>) {
+        self.check_same_dims(rhs.dims);
+        for (idx, e) in rhs.indexed_iter() {
+            self[idx] = self[idx].clone() + e.clone();
+        }
+    }
+}
+
+impl<T: Num + Clone, A: Allocator> SubAssign<&Matrix<T, A>> for Matrix<T, A> {
+    fn sub_assign(&mut self, rhs: &Matrix<T, A>) {
+        self.check_same_dims(rhs.dims);
+        for (idx, e) in rhs.indexed_iter() {
+            self[idx] = self[idx].clone() - e.clone();
+        }
+    }
+}
+
+/* Matrix product: (m x k) * (k x n) gives m x n */
+impl<T: Num + Clone, A: Allocator + Clone> Mul for &Matrix<T, A> {
+    type Output = Matrix<T, A>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let ((m, k), (p, n)) = (self.dims, rhs.dims);
+        assert_eq!(k, p, "cannot multiply {}x{} by {}x{} matrix", m, k, p, n);
+        Matrix::from_fn_in(m, n, self.order, self.alloc.clone(), |i, j| {
+            self.row(i).zip(rhs.col(j))
+                .fold(T::zero(), |acc, (a, b)| acc + a.clone() * b.clone())
+        })
+    }
+}
+
+/* Scalar operations consume the matrix and work in place */
+macro_rules! scalar_op {
+    ($op:ident, $f:ident) => {
+        impl<T: Num + Clone, A: Allocator> $op<T> for Matrix<T, A> {
+            type Output = Matrix<T, A>;
+            fn $f(mut self, k: T) -> Self::Output {
+                for e in self.iter_mut() {
+                    *e = e.clone().$f(k.clone());
+                }
+                self
+            }
+        }
+    };
+}
+
+scalar_op!(Add, add);
+scalar_op!(Sub, sub);
+scalar_op!(Mul, mul);
+scalar_op!(Div, div);
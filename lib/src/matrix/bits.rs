@@ -0,0 +1,269 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not};
+
+use super::{debug_grid, Matrix};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/* Boolean matrix packed 64 cells to a word. Storage is row-major with every
+ * row starting on a fresh word; bits past the last column are kept clear so
+ * whole words can be compared, counted and combined. */
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitMatrix {
+    dims: (usize, usize),
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    pub fn new(m: usize, n: usize) -> Self {
+        let words_per_row = n.div_ceil(WORD_BITS);
+        BitMatrix { dims: (m, n), words_per_row, words: vec![0; m*words_per_row] }
+    }
+
+    pub fn new_filled(m: usize, n: usize, e: bool) -> Self {
+        let mut b = Self::new(m, n);
+        b.fill(e);
+        b
+    }
+
+    pub fn from_fn<F>(m: usize, n: usize, mut f: F) -> Self
+        where F: FnMut(usize, usize) -> bool
+    {
+        let mut b = Self::new(m, n);
+        for i in 0..m {
+            for j in 0..n {
+                if f(i, j) {
+                    b.set((i, j), true);
+                }
+            }
+        }
+        b
+    }
+
+    pub fn from_matrix<A: super::Allocator>(g: &Matrix<bool, A>) -> Self {
+        let (m, n) = g.get_dims();
+        Self::from_fn(m, n, |i, j| g[(i, j)])
+    }
+
+    pub fn to_matrix(&self) -> Matrix<bool> {
+        let (m, n) = self.dims;
+        Matrix::from_fn(m, n, |i, j| self[(i, j)])
+    }
+
+    pub fn get_dims(&self) -> (usize, usize) {
+        self.dims
+    }
+
+    pub fn in_bounds(&self, (i, j): (usize, usize)) -> bool {
+        let (m, n) = self.dims;
+        i < m && j < n
+    }
+
+    pub fn get(&self, idx: (usize, usize)) -> Option<bool> {
+        if self.in_bounds(idx) {
+            let (w, bit) = self.locate(idx);
+            Some(self.words[w] & bit != 0)
+        } else {
+            None
+        }
+    }
+
+    /* Returns the previous value */
+    pub fn set(&mut self, idx: (usize, usize), e: bool) -> bool {
+        self.check_bounds(idx);
+        let (w, bit) = self.locate(idx);
+        let old = self.words[w] & bit != 0;
+        if e {
+            self.words[w] |= bit;
+        } else {
+            self.words[w] &= !bit;
+        }
+        old
+    }
+
+    pub fn toggle(&mut self, idx: (usize, usize)) {
+        self.check_bounds(idx);
+        let (w, bit) = self.locate(idx);
+        self.words[w] ^= bit;
+    }
+
+    pub fn fill(&mut self, e: bool) {
+        self.words.fill(if e { !0 } else { 0 });
+        self.clear_padding();
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn count_ones_row(&self, i: usize) -> usize {
+        self.row_words(i).iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn count_ones_col(&self, j: usize) -> usize {
+        self.col(j).filter(|&b| b).count()
+    }
+
+    pub fn row_counts(&self) -> Vec<usize> {
+        let (m, _) = self.dims;
+        (0..m).map(|i| self.count_ones_row(i)).collect()
+    }
+
+    pub fn col_counts(&self) -> Vec<usize> {
+        let (_, n) = self.dims;
+        let mut counts = vec![0; n];
+        for (_, j) in self.ones() {
+            counts[j] += 1;
+        }
+        counts
+    }
+
+    /* Row-major order */
+    pub fn iter(&self) -> impl Iterator<Item=bool> + '_ {
+        self.rows().flatten()
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item=((usize, usize), bool)> + '_ {
+        let (m, n) = self.dims;
+        (0..m).flat_map(move |i| (0..n).map(move |j| ((i, j), self[(i, j)])))
+    }
+
+    /* Indices of the set cells, skipping empty words */
+    pub fn ones(&self) -> impl Iterator<Item=(usize, usize)> + '_ {
+        let wpr = self.words_per_row;
+        self.words.iter().enumerate().flat_map(move |(k, &w)| {
+            let (i, j0) = (k / wpr, (k % wpr) * WORD_BITS);
+            let mut w = w;
+            std::iter::from_fn(move || {
+                if w == 0 {
+                    return None;
+                }
+                let b = w.trailing_zeros() as usize;
+                w &= w - 1;
+                Some((i, j0 + b))
+            })
+        })
+    }
+
+    pub fn row(&self, i: usize) -> impl Iterator<Item=bool> + '_ {
+        let (m, n) = self.dims;
+        assert!(i < m, "row {} out of bounds for {}x{} matrix", i, m, n);
+        (0..n).map(move |j| self[(i, j)])
+    }
+
+    pub fn col(&self, j: usize) -> impl Iterator<Item=bool> + '_ {
+        let (m, n) = self.dims;
+        assert!(j < n, "column {} out of bounds for {}x{} matrix", j, m, n);
+        (0..m).map(move |i| self[(i, j)])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item=impl Iterator<Item=bool> + '_> + '_ {
+        let (m, _) = self.dims;
+        (0..m).map(|i| self.row(i))
+    }
+
+    pub fn cols(&self) -> impl Iterator<Item=impl Iterator<Item=bool> + '_> + '_ {
+        let (_, n) = self.dims;
+        (0..n).map(|j| self.col(j))
+    }
+
+    /* One line per row, each terminated by a newline */
+    pub fn render<F>(&self, mut f: F) -> String
+        where F: FnMut(bool) -> char
+    {
+        let mut s = String::new();
+        for row in self.rows() {
+            s.extend(row.map(&mut f));
+            s.push('\n');
+        }
+        s
+    }
+
+    fn locate(&self, (i, j): (usize, usize)) -> (usize, u64) {
+        (i*self.words_per_row + j / WORD_BITS, 1 << (j % WORD_BITS))
+    }
+
+    fn row_words(&self, i: usize) -> &[u64] {
+        let (m, n) = self.dims;
+        assert!(i < m, "row {} out of bounds for {}x{} matrix", i, m, n);
+        &self.words[i*self.words_per_row..(i+1)*self.words_per_row]
+    }
+
+    fn clear_padding(&mut self) {
+        let (_, n) = self.dims;
+        let tail = n % WORD_BITS;
+        if tail == 0 {
+            return;
+        }
+        let mask = (1u64 << tail) - 1;
+        for row in self.words.chunks_mut(self.words_per_row) {
+            *row.last_mut().unwrap() &= mask;
+        }
+    }
+
+    fn check_bounds(&self, idx: (usize, usize)) {
+        if !self.in_bounds(idx) {
+            let (m, n) = self.dims;
+            panic!("index {:?} out of bounds for {}x{} matrix", idx, m, n);
+        }
+    }
+
+    fn zip_words<F>(&mut self, rhs: &Self, f: F)
+        where F: Fn(&mut u64, u64)
+    {
+        assert_eq!(self.dims, rhs.dims, "mismatched bit matrix dimensions");
+        for (a, &b) in self.words.iter_mut().zip(rhs.words.iter()) {
+            f(a, b);
+        }
+    }
+}
+
+impl Index<(usize, usize)> for BitMatrix {
+    type Output = bool;
+    fn index(&self, idx: (usize, usize)) -> &Self::Output {
+        self.check_bounds(idx);
+        if self.get(idx).unwrap() { &true } else { &false }
+    }
+}
+
+macro_rules! bit_op {
+    ($op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $w:expr) => {
+        impl $op_assign<&BitMatrix> for BitMatrix {
+            fn $f_assign(&mut self, rhs: &BitMatrix) {
+                self.zip_words(rhs, $w);
+            }
+        }
+
+        impl $op<&BitMatrix> for &BitMatrix {
+            type Output = BitMatrix;
+            fn $f(self, rhs: &BitMatrix) -> BitMatrix {
+                let mut b = self.clone();
+                b.$f_assign(rhs);
+                b
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| *a &= b);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| *a |= b);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| *a ^= b);
+
+impl Not for &BitMatrix {
+    type Output = BitMatrix;
+    fn not(self) -> BitMatrix {
+        let mut b = self.clone();
+        for w in b.words.iter_mut() {
+            *w = !*w;
+        }
+        b.clear_padding();
+        b
+    }
+}
+
+impl fmt::Debug for BitMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_grid(f, "BitMatrix", self.dims, |idx| &self[idx])
+    }
+}
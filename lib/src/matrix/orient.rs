@@ -0,0 +1,151 @@
+use super::{Allocator, Matrix, Order};
+
+/* Re-orientation. Everything here moves elements rather than cloning them;
+ * clone first for a copying version. */
+impl<T, A: Allocator> Matrix<T, A> {
+    /* Re-view the same storage with rows and columns exchanged. O(1). */
+    pub fn swap_axes(self) -> Self {
+        let mut u = self.into_uninit();
+        let (m, n) = u.dims;
+        u.dims = (n, m);
+        u.order = u.order.flipped();
+        unsafe { u.assume_init() }
+    }
+
+    /* Mirror left to right */
+    pub fn flip_h(&mut self) {
+        let (m, n) = self.dims;
+        for i in 0..m {
+            for j in 0..n/2 {
+                let (a, b) = (self.offset((i, j)), self.offset((i, n-1-j)));
+                self.as_mut_slice().swap(a, b);
+            }
+        }
+    }
+
+    /* Mirror top to bottom */
+    pub fn flip_v(&mut self) {
+        let (m, n) = self.dims;
+        for i in 0..m/2 {
+            for j in 0..n {
+                let (a, b) = (self.offset((i, j)), self.offset((m-1-i, j)));
+                self.as_mut_slice().swap(a, b);
+            }
+        }
+    }
+}
+
+impl<T, A: Allocator + Clone> Matrix<T, A> {
+    pub fn with_order(self, order: Order) -> Self {
+        if order == self.order {
+            return self;
+        }
+        let dims = self.dims;
+        self.rearranged(dims, order, |i, j| (i, j))
+    }
+
+    /* As swap_axes, but keeping the storage order */
+    pub fn transpose(self) -> Self {
+        let order = self.order;
+        self.swap_axes().with_order(order)
+    }
+
+    pub fn rotate_cw(self) -> Self {
+        let (m, n) = self.dims;
+        let order = self.order;
+        self.rearranged((n, m), order, |i, j| (m-1-j, i))
+    }
+
+    pub fn rotate_ccw(self) -> Self {
+        let (m, n) = self.dims;
+        let order = self.order;
+        self.rearranged((n, m), order, |i, j| (j, n-1-i))
+    }
+
+    /* Build a dims-sized matrix whose (i, j) element is moved out of
+     * self[src(i, j)]. src must be a bijection. */
+    fn rearranged<F>(self, (m, n): (usize, usize), order: Order, mut src: F) -> Self
+        where F: FnMut(usize, usize) -> (usize, usize)
+    {
+        let alloc = self.alloc.clone();
+        let old = self.into_uninit();
+        let mut new = Self::new_uninit_in(m, n, order, alloc);
+        for k in 0..m*n {
+            let (i, j) = new.index_of(k);
+            let e = unsafe { old[src(i, j)].assume_init_read() };
+            new.as_mut_slice()[k].write(e);
+        }
+        unsafe { new.assume_init() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::testing::{counted, ids, DropCounter};
+
+    #[test]
+    fn rotate_moves_every_element_once() {
+        for order in [Order::ColMajor, Order::RowMajor] {
+            let dc = DropCounter::default();
+            let g = counted(&dc, 2, 3, order).rotate_cw();
+            assert_eq!(dc.drops(), 0);
+            assert_eq!(g.order(), order);
+            assert_eq!(ids(&g), [[10, 0], [11, 1], [12, 2]]);
+
+            let g = g.rotate_ccw().rotate_ccw();
+            assert_eq!(dc.drops(), 0);
+            assert_eq!(ids(&g), [[2, 12], [1, 11], [0, 10]]);
+
+            drop(g);
+            assert_eq!(dc.drops(), 6);
+        }
+    }
+
+    #[test]
+    fn transpose_moves_every_element_once() {
+        for order in [Order::ColMajor, Order::RowMajor] {
+            let dc = DropCounter::default();
+            let g = counted(&dc, 2, 3, order).transpose();
+            assert_eq!(g.order(), order);
+            assert_eq!(ids(&g), [[0, 10], [1, 11], [2, 12]]);
+
+            let g = g.swap_axes();
+            assert_eq!(g.order(), order.flipped());
+            assert_eq!(ids(&g), [[0, 1, 2], [10, 11, 12]]);
+
+            let g = g.with_order(order);
+            assert_eq!(dc.drops(), 0);
+            assert_eq!(ids(&g), [[0, 1, 2], [10, 11, 12]]);
+
+            drop(g);
+            assert_eq!(dc.drops(), 6);
+        }
+    }
+
+    #[test]
+    fn flips_swap_in_place() {
+        let dc = DropCounter::default();
+        let mut g = counted(&dc, 3, 2, Order::ColMajor);
+        g.flip_h();
+        assert_eq!(ids(&g), [[1, 0], [11, 10], [21, 20]]);
+        g.flip_v();
+        assert_eq!(ids(&g), [[21, 20], [11, 10], [1, 0]]);
+        assert_eq!(dc.drops(), 0);
+
+        drop(g);
+        assert_eq!(dc.drops(), 6);
+    }
+
+    #[test]
+    fn from_vec_row_major_takes_the_buffer() {
+        let dc = DropCounter::default();
+        let v = (0..6).map(|k| dc.make(10*(k / 3) + k % 3)).collect();
+        let g = Matrix::from_vec_ordered(2, 3, Order::RowMajor, v);
+        assert_eq!(dc.drops(), 0);
+        assert_eq!(ids(&g), [[0, 1, 2], [10, 11, 12]]);
+
+        drop(g);
+        assert_eq!(dc.drops(), 6);
+    }
+}
@@ -0,0 +1,79 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+use super::{index_of, Allocator, Matrix, MatrixViewMut, Rect};
+
+/* Threads to split work across; at least one, and no more than jobs */
+fn workers(jobs: usize) -> usize {
+    thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(jobs)
+        .max(1)
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    /* Call f on every row, as a 1 x n view, with rows shared out across
+     * threads in contiguous bands */
+    pub fn par_rows_mut<F>(&mut self, f: F)
+        where T: Send,
+              F: Fn(usize, MatrixViewMut<'_, T>) + Sync
+    {
+        let (m, n) = self.dims;
+        let band = m.div_ceil(workers(m));
+        let f = &f;
+
+        thread::scope(|s| {
+            let mut rest = self.view_mut(Rect::new((0, 0), (m, n)));
+            let mut start = 0;
+            while start < m {
+                let (this, next) = rest.split_at_row(band.min(m - start));
+                rest = next;
+                s.spawn(move || {
+                    let mut this = this;
+                    for i in 0..this.get_dims().0 {
+                        f(start + i, this.view_mut(Rect::new((i, 0), (1, n))));
+                    }
+                });
+                start += band;
+            }
+        });
+    }
+
+    pub fn par_map<U, F>(&self, f: F) -> Matrix<U>
+        where T: Sync,
+              U: Send,
+              F: Fn(&T) -> U + Sync
+    {
+        self.par_map_indexed(|_, e| f(e))
+    }
+
+    /* Result has the same storage order. Cells are shared out across threads
+     * in contiguous runs of storage. */
+    pub fn par_map_indexed<U, F>(&self, f: F) -> Matrix<U>
+        where T: Sync,
+              U: Send,
+              F: Fn((usize, usize), &T) -> U + Sync
+    {
+        let (dims, order) = (self.dims, self.order);
+        let (m, n) = dims;
+        let len = self.len();
+        let chunk = len.div_ceil(workers(len)).max(1);
+        let f = &f;
+
+        /* A panicking thread leaks what it has written, but never lets an
+         * uninitialised element be dropped */
+        let mut u = Matrix::<U>::new_uninit_ordered(m, n, order);
+        thread::scope(|s| {
+            let src = self.as_slice().chunks(chunk);
+            let dst = u.as_mut_slice().chunks_mut(chunk);
+            for (c, (src, dst)) in src.zip(dst).enumerate() {
+                s.spawn(move || {
+                    for (k, (e, out)) in src.iter().zip(dst).enumerate() {
+                        out.write(f(index_of(dims, order, c*chunk + k), e));
+                    }
+                });
+            }
+        });
+        unsafe { u.assume_init() }
+    }
+}
@@ -0,0 +1,77 @@
+use num::Num;
+
+use super::{Allocator, Direction, Matrix, Rect};
+
+/* Summed-area table: sums over any rectangle in O(1) after an O(mn) build */
+#[derive(Clone, Debug)]
+pub struct SummedArea<T> {
+    /* (m+1) x (n+1), row and column 0 are zero; sums[(i, j)] holds the sum
+     * of everything above and left of (i, j) */
+    sums: Matrix<T>,
+}
+
+impl<T: Num + Clone> SummedArea<T> {
+    pub fn new<A: Allocator>(g: &Matrix<T, A>) -> Self {
+        let (m, n) = g.get_dims();
+        let mut sums = Matrix::new_filled(m+1, n+1, T::zero());
+        for i in 0..m {
+            for j in 0..n {
+                sums[(i+1, j+1)] = g[(i, j)].clone()
+                                 + sums[(i, j+1)].clone()
+                                 + sums[(i+1, j)].clone()
+                                 - sums[(i, j)].clone();
+            }
+        }
+        SummedArea { sums }
+    }
+
+    pub fn sum(&self, r: Rect) -> T {
+        let (m, n) = self.get_dims();
+        assert!(r.fits_in((m, n)), "{:?} out of bounds for {}x{} matrix", r, m, n);
+        let ((i0, j0), (h, w)) = (r.origin, r.dims);
+        let (i1, j1) = (i0 + h, j0 + w);
+        let s = &self.sums;
+        /* Add before subtracting so unsigned sums never underflow */
+        (s[(i1, j1)].clone() + s[(i0, j0)].clone())
+            - (s[(i0, j1)].clone() + s[(i1, j0)].clone())
+    }
+
+    /* Dimensions of the summed matrix */
+    pub fn get_dims(&self) -> (usize, usize) {
+        let (m, n) = self.sums.get_dims();
+        (m - 1, n - 1)
+    }
+}
+
+impl<T: PartialOrd + Clone, A: Allocator + Clone> Matrix<T, A> {
+    /* Each cell holds the maximum of itself and every cell beyond it in
+     * direction d, e.g. Direction::W gives the max of the row up to and
+     * including that column. */
+    pub fn running_max(&self, d: Direction) -> Self {
+        self.running(d, |a, b| if b > a { b.clone() } else { a.clone() })
+    }
+
+    pub fn running_min(&self, d: Direction) -> Self {
+        self.running(d, |a, b| if b < a { b.clone() } else { a.clone() })
+    }
+
+    fn running<F>(&self, d: Direction, f: F) -> Self
+        where F: Fn(&T, &T) -> T
+    {
+        let (m, n) = self.dims;
+        let (di, dj) = d.delta();
+        let mut out = self.clone();
+
+        /* Visit cells so that the one in direction d is always done first */
+        let rows: Vec<usize> = if di > 0 { (0..m).rev().collect() } else { (0..m).collect() };
+        let cols: Vec<usize> = if dj > 0 { (0..n).rev().collect() } else { (0..n).collect() };
+        for &i in rows.iter() {
+            for &j in cols.iter() {
+                if let Some(prev) = self.step((i, j), (di, dj)) {
+                    out[(i, j)] = f(&self[(i, j)], &out[prev]);
+                }
+            }
+        }
+        out
+    }
+}
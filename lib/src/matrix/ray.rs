@@ -0,0 +1,88 @@
+use super::{step, Allocator, Matrix};
+
+/* Compass directions, with north towards row 0 and west towards column 0 */
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    N, NE, E, SE, S, SW, W, NW
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [Direction::N, Direction::NE,
+                                     Direction::E, Direction::SE,
+                                     Direction::S, Direction::SW,
+                                     Direction::W, Direction::NW];
+
+    pub const ORTHOGONAL: [Direction; 4] = [Direction::N, Direction::E,
+                                            Direction::S, Direction::W];
+
+    /* (row, column) offset of a single step */
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::N  => (-1,  0),
+            Direction::NE => (-1,  1),
+            Direction::E  => ( 0,  1),
+            Direction::SE => ( 1,  1),
+            Direction::S  => ( 1,  0),
+            Direction::SW => ( 1, -1),
+            Direction::W  => ( 0, -1),
+            Direction::NW => (-1, -1),
+        }
+    }
+}
+
+/* Indices walked from (but excluding) a start cell up to the edge */
+pub struct Ray {
+    dims: (usize, usize),
+    pos: (usize, usize),
+    delta: (isize, isize),
+}
+
+impl Iterator for Ray {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = step(self.dims, self.pos, self.delta)?;
+        self.pos = next;
+        Some(next)
+    }
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn ray(&self, idx: (usize, usize), d: Direction) -> Ray {
+        Ray { dims: self.dims, pos: idx, delta: d.delta() }
+    }
+}
+
+/* Like take_while, but also yields the first item that fails, which is
+ * what a line of sight needs: the blocking cell is still seen. */
+pub struct TakeUntilBlocking<I, P> {
+    iter: I,
+    blocks: P,
+    done: bool,
+}
+
+impl<I, P> Iterator for TakeUntilBlocking<I, P>
+    where I: Iterator,
+          P: FnMut(&I::Item) -> bool
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let e = self.iter.next()?;
+        self.done = (self.blocks)(&e);
+        Some(e)
+    }
+}
+
+pub trait TakeUntilBlockingExt: Iterator + Sized {
+    fn take_until_blocking<P>(self, blocks: P) -> TakeUntilBlocking<Self, P>
+        where P: FnMut(&Self::Item) -> bool
+    {
+        TakeUntilBlocking { iter: self, blocks, done: false }
+    }
+}
+
+impl<I: Iterator> TakeUntilBlockingExt for I {}
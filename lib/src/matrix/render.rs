@@ -0,0 +1,86 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use super::{Allocator, Matrix};
+
+/* Plain netpbm files ask for lines of at most 70 characters */
+const PNM_LINE_WIDTH: usize = 70;
+
+impl<T, A: Allocator> Matrix<T, A> {
+    /* One line per row, each terminated by a newline */
+    pub fn render<F>(&self, mut f: F) -> String
+        where F: FnMut(&T) -> char
+    {
+        let mut s = String::new();
+        for row in self.rows() {
+            s.extend(row.map(&mut f));
+            s.push('\n');
+        }
+        s
+    }
+
+    /* Plain (P1) bitmap, with set cells drawn black */
+    pub fn write_pbm<W, F>(&self, w: &mut W, mut f: F) -> io::Result<()>
+        where W: Write,
+              F: FnMut(&T) -> bool
+    {
+        let (m, n) = self.get_dims();
+        writeln!(w, "P1")?;
+        writeln!(w, "{} {}", n, m)?;
+        self.write_pnm_samples(w, |e| f(e) as u16)
+    }
+
+    /* Plain (P2) greymap, with samples between 0 and maxval. PGM needs
+     * maxval to be at least 1. */
+    pub fn write_pgm<W, F>(&self, w: &mut W, maxval: u16, mut f: F) -> io::Result<()>
+        where W: Write,
+              F: FnMut(&T) -> u16
+    {
+        if maxval == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "PGM maxval must be positive"));
+        }
+        let (m, n) = self.get_dims();
+        writeln!(w, "P2")?;
+        writeln!(w, "{} {}", n, m)?;
+        writeln!(w, "{}", maxval)?;
+        self.write_pnm_samples(w, |e| f(e).min(maxval))
+    }
+
+    fn write_pnm_samples<W, F>(&self, w: &mut W, mut f: F) -> io::Result<()>
+        where W: Write,
+              F: FnMut(&T) -> u16
+    {
+        for row in self.rows() {
+            let mut line = String::new();
+            for e in row {
+                let sample = f(e).to_string();
+                if !line.is_empty() && line.len() + 1 + sample.len() > PNM_LINE_WIDTH {
+                    writeln!(w, "{}", line)?;
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&sample);
+            }
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/* Rows on separate lines, cells unseparated. Formatting options such as
+ * width are applied to every cell, so `{:3}` lines up numeric grids. */
+impl<T: fmt::Display, A: Allocator> fmt::Display for Matrix<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for e in row {
+                e.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::ptr;
+
+use super::{Allocator, Matrix, Rect};
+
+/* Changing the extent of a matrix. Surviving elements are moved, not
+ * cloned; new cells take a clone of `fill`. */
+impl<T: Clone, A: Allocator + Clone> Matrix<T, A> {
+    pub fn pad(self, top: usize, bottom: usize, left: usize, right: usize, fill: T) -> Self {
+        let (m, n) = self.dims;
+        self.reframe((top + m + bottom, left + n + right),
+                     (top as isize, left as isize),
+                     || fill.clone())
+    }
+
+    /* Existing contents stay anchored at the top-left corner */
+    pub fn resize(self, m: usize, n: usize, fill: T) -> Self {
+        self.reframe((m, n), (0, 0), || fill.clone())
+    }
+}
+
+impl<T, A: Allocator + Clone> Matrix<T, A> {
+    pub fn crop(self, r: Rect) -> Self {
+        let (m, n) = self.dims;
+        assert!(r.fits_in(self.dims), "{:?} out of bounds for {}x{} matrix", r, m, n);
+        let (i, j) = r.origin;
+        self.reframe(r.dims, (-(i as isize), -(j as isize)),
+                     || unreachable!("cropped cell outside the matrix"))
+    }
+
+    /* Strip the outer rows and columns in which every cell satisfies pred */
+    pub fn trim_where<P>(self, pred: P) -> Self
+        where P: FnMut(&T) -> bool
+    {
+        let r = self.bounds_where_not(pred).unwrap_or_default();
+        self.crop(r)
+    }
+
+    /* Smallest rectangle holding every cell that fails pred */
+    fn bounds_where_not<P>(&self, mut pred: P) -> Option<Rect>
+        where P: FnMut(&T) -> bool
+    {
+        let mut corners: Option<((usize, usize), (usize, usize))> = None;
+        for ((i, j), e) in self.indexed_iter() {
+            if pred(e) {
+                continue;
+            }
+            corners = Some(match corners {
+                None => ((i, j), (i, j)),
+                Some(((i0, j0), (i1, j1))) => ((i0.min(i), j0.min(j)),
+                                               (i1.max(i), j1.max(j))),
+            });
+        }
+        corners.map(|(a, b)| Rect::from_corners(a, b))
+    }
+
+    /* New matrix of the given dims, where cell (i, j) is moved out of
+     * self[(i - di, j - dj)] when that exists and made by fill otherwise.
+     * Elements that land outside the new dims are dropped. */
+    fn reframe<F>(self, (m, n): (usize, usize), (di, dj): (isize, isize), mut fill: F) -> Self
+        where F: FnMut() -> T
+    {
+        let (order, alloc) = (self.order, self.alloc.clone());
+        let mut old = self.into_uninit();
+        let new = Matrix::from_fn_in(m, n, order, alloc, |i, j| {
+            let src = (i as isize - di, j as isize - dj);
+            match (usize::try_from(src.0), usize::try_from(src.1)) {
+                (Ok(si), Ok(sj)) if old.in_bounds((si, sj)) =>
+                    unsafe { old[(si, sj)].assume_init_read() },
+                _ => fill(),
+            }
+        });
+
+        let (om, on) = old.dims;
+        for sj in 0..on {
+            for si in 0..om {
+                let (i, j) = (si as isize + di, sj as isize + dj);
+                let kept = i >= 0 && j >= 0 && (i as usize) < m && (j as usize) < n;
+                if !kept {
+                    unsafe { ptr::drop_in_place(old[(si, sj)].as_mut_ptr()) };
+                }
+            }
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::testing::{counted, ids, DropCounter};
+    use crate::matrix::Order;
+
+    #[test]
+    fn pad_moves_old_and_clones_fill() {
+        for order in [Order::ColMajor, Order::RowMajor] {
+            let dc = DropCounter::default();
+            let g = counted(&dc, 2, 2, order).pad(1, 0, 0, 1, dc.make(99));
+            /* Only the fill itself has gone */
+            assert_eq!(dc.drops(), 1);
+            assert_eq!(ids(&g), [[99, 99, 99], [0, 1, 99], [10, 11, 99]]);
+
+            drop(g);
+            assert_eq!(dc.drops(), 1 + 9);
+        }
+    }
+
+    #[test]
+    fn crop_drops_what_is_cut_off() {
+        for order in [Order::ColMajor, Order::RowMajor] {
+            let dc = DropCounter::default();
+            let g = counted(&dc, 3, 3, order).crop(Rect::new((1, 1), (2, 2)));
+            assert_eq!(dc.drops(), 5);
+            assert_eq!(ids(&g), [[11, 12], [21, 22]]);
+
+            drop(g);
+            assert_eq!(dc.drops(), 9);
+        }
+    }
+
+    #[test]
+    fn resize_drops_and_fills() {
+        let dc = DropCounter::default();
+        let g = counted(&dc, 2, 3, Order::ColMajor).resize(3, 2, dc.make(99));
+        /* Column 2, and the fill */
+        assert_eq!(dc.drops(), 3);
+        assert_eq!(ids(&g), [[0, 1], [10, 11], [99, 99]]);
+
+        drop(g);
+        assert_eq!(dc.drops(), 3 + 6);
+    }
+
+    #[test]
+    fn trim_where_drops_the_border() {
+        let dc = DropCounter::default();
+        let g = Matrix::from_fn(4, 4, |i, j| {
+            dc.make(if (1..=2).contains(&i) && j == 1 { 10*i + j } else { 0 })
+        });
+        let g = g.trim_where(|e| e.id == 0);
+        assert_eq!(dc.drops(), 14);
+        assert_eq!(ids(&g), [[11], [21]]);
+
+        let g = g.trim_where(|_| true);
+        assert_eq!(g.get_dims(), (0, 0));
+        assert_eq!(dc.drops(), 16);
+    }
+}
@@ -0,0 +1,376 @@
+/* Saving and loading matrices.
+ *
+ * The binary format is a 24 byte header followed by the elements, with
+ * every multi-byte value little-endian:
+ *
+ *   offset  size  field
+ *        0     4  magic, b"AOCM"
+ *        4     1  format version, currently 1
+ *        5     1  storage order: 0 column-major, 1 row-major
+ *        6     1  element width in bytes
+ *        7     1  reserved, zero
+ *        8     8  rows (u64)
+ *       16     8  columns (u64)
+ *       24     -  rows * columns elements, in storage order
+ *
+ * usize and isize are always stored as 8 bytes so files move between
+ * platforms. Reading checks the width against the element type, but
+ * cannot tell apart types of the same width.
+ *
+ * The text format is exactly the output of render: one line per row, one
+ * character per cell. Only the line terminator is stripped on reading, so
+ * cells rendered as spaces survive the round trip.
+ */
+use std::io::{self, BufRead, Read, Write};
+use std::mem::MaybeUninit;
+
+use super::{Allocator, Matrix, Order};
+use crate::parser::{bad_cell, ragged};
+
+const MAGIC: &[u8; 4] = b"AOCM";
+const VERSION: u8 = 1;
+
+/* Elements with a fixed-size little-endian encoding, between 1 and 255
+ * bytes wide */
+pub trait FixedWidth: Sized {
+    const WIDTH: usize;
+
+    fn write_le(&self, out: &mut Vec<u8>);
+
+    /* None if the bytes are not a valid Self. b has length WIDTH. */
+    fn read_le(b: &[u8]) -> Option<Self>;
+}
+
+macro_rules! fixed_width {
+    ($($t:ty),*) => {
+        $(
+            impl FixedWidth for $t {
+                const WIDTH: usize = std::mem::size_of::<$t>();
+
+                fn write_le(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(b: &[u8]) -> Option<Self> {
+                    Some(<$t>::from_le_bytes(b.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+fixed_width!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl FixedWidth for usize {
+    const WIDTH: usize = 8;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        (*self as u64).write_le(out)
+    }
+
+    fn read_le(b: &[u8]) -> Option<Self> {
+        u64::read_le(b)?.try_into().ok()
+    }
+}
+
+impl FixedWidth for isize {
+    const WIDTH: usize = 8;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        (*self as i64).write_le(out)
+    }
+
+    fn read_le(b: &[u8]) -> Option<Self> {
+        i64::read_le(b)?.try_into().ok()
+    }
+}
+
+impl FixedWidth for bool {
+    const WIDTH: usize = 1;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8)
+    }
+
+    fn read_le(b: &[u8]) -> Option<Self> {
+        match b {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl FixedWidth for char {
+    const WIDTH: usize = 4;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        (*self as u32).write_le(out)
+    }
+
+    fn read_le(b: &[u8]) -> Option<Self> {
+        char::from_u32(u32::read_le(b)?)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<T: FixedWidth, A: Allocator> Matrix<T, A> {
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let (m, n) = self.dims;
+        let order = match self.order {
+            Order::ColMajor => 0,
+            Order::RowMajor => 1,
+        };
+
+        let mut buf = Vec::with_capacity(24 + self.len()*T::WIDTH);
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&[VERSION, order, T::WIDTH as u8, 0]);
+        (m as u64).write_le(&mut buf);
+        (n as u64).write_le(&mut buf);
+        for e in self.iter() {
+            e.write_le(&mut buf);
+        }
+        w.write_all(&buf)
+    }
+}
+
+impl<T: FixedWidth> Matrix<T> {
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut header = [0; 24];
+        r.read_exact(&mut header)?;
+
+        if &header[0..4] != MAGIC {
+            return Err(invalid("not a matrix file".to_string()));
+        }
+        if header[4] != VERSION {
+            return Err(invalid(format!("unsupported matrix format version {}", header[4])));
+        }
+        let order = match header[5] {
+            0 => Order::ColMajor,
+            1 => Order::RowMajor,
+            o => return Err(invalid(format!("unknown storage order {}", o))),
+        };
+        if header[6] as usize != T::WIDTH {
+            return Err(invalid(format!("element width is {} bytes, expected {}",
+                                       header[6], T::WIDTH)));
+        }
+
+        let dim = |b: &[u8]| u64::read_le(b).and_then(|d| usize::try_from(d).ok());
+        let (m, n) = match (dim(&header[8..16]), dim(&header[16..24])) {
+            (Some(m), Some(n)) => (m, n),
+            _ => return Err(invalid("matrix dimensions too large".to_string())),
+        };
+        let len = m.checked_mul(n)
+                   .and_then(|l| l.checked_mul(T::WIDTH))
+                   .ok_or_else(|| invalid(format!("{}x{} matrix is too large", m, n)))?;
+
+        /* Read before allocating, so a corrupt header cannot ask for more
+         * memory than the file holds */
+        let mut data = Vec::new();
+        r.take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                      format!("{}x{} matrix is truncated", m, n)));
+        }
+
+        let elems = data.chunks_exact(T::WIDTH)
+                        .map(T::read_le)
+                        .collect::<Option<Vec<T>>>()
+                        .ok_or_else(|| invalid("invalid element in matrix".to_string()))?;
+
+        let mut u: Matrix<MaybeUninit<T>> = Matrix::new_uninit_ordered(m, n, order);
+        for (cell, e) in u.iter_mut().zip(elems) {
+            cell.write(e);
+        }
+        Ok(unsafe { u.assume_init() })
+    }
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn write_text<W, F>(&self, w: &mut W, f: F) -> io::Result<()>
+        where W: Write,
+              F: FnMut(&T) -> char
+    {
+        w.write_all(self.render(f).as_bytes())
+    }
+}
+
+impl<T> Matrix<T> {
+    /* Inverse of write_text: every line up to EOF is a row */
+    pub fn read_text<R, F>(r: &mut R, mut f: F) -> io::Result<Self>
+        where R: BufRead,
+              F: FnMut(char) -> Option<T>
+    {
+        let mut cells = vec![];
+        let (mut m, mut n) = (0, 0);
+
+        for line in r.lines() {
+            let line = line?;
+
+            let width = line.chars().count();
+            if m == 0 {
+                n = width;
+            } else if width != n {
+                return Err(ragged(m+1, &line, n).into());
+            }
+
+            for (j, c) in line.chars().enumerate() {
+                cells.push(f(c).ok_or_else(|| io::Error::from(bad_cell(m+1, j, c)))?);
+            }
+            m += 1;
+        }
+
+        Ok(Matrix::from_vec(m, n, cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Matrix<i32> {
+        Matrix::from_fn(3, 4, |i, j| i as i32 * 10 - j as i32)
+    }
+
+    fn header(m: u64, n: u64) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        buf.extend_from_slice(&[VERSION, 0, 4, 0]);
+        buf.extend_from_slice(&m.to_le_bytes());
+        buf.extend_from_slice(&n.to_le_bytes());
+        buf
+    }
+
+    fn read_err(buf: &[u8]) -> io::Error {
+        Matrix::<i32>::read_from(&mut &buf[..]).unwrap_err()
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let g = sample();
+        let mut buf = vec![];
+        g.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), 24 + 12*4);
+        assert_eq!(Matrix::<i32>::read_from(&mut &buf[..]).unwrap(), g);
+    }
+
+    #[test]
+    fn binary_round_trip_row_major() {
+        let v: Vec<usize> = (0..6).collect();
+        let g = Matrix::from_vec_ordered(2, 3, Order::RowMajor, v);
+        let mut buf = vec![];
+        g.write_to(&mut buf).unwrap();
+        assert_eq!(buf[5], 1);
+
+        let h = Matrix::<usize>::read_from(&mut &buf[..]).unwrap();
+        assert_eq!(h.order(), Order::RowMajor);
+        assert_eq!(h, g);
+    }
+
+    #[test]
+    fn binary_round_trip_empty() {
+        for (m, n) in [(0, 0), (0, 5), (5, 0)] {
+            let g = Matrix::<char>::new_filled(m, n, 'x');
+            let mut buf = vec![];
+            g.write_to(&mut buf).unwrap();
+            assert_eq!(buf.len(), 24);
+
+            let h = Matrix::<char>::read_from(&mut &buf[..]).unwrap();
+            assert_eq!(h.get_dims(), (m, n));
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let g = Matrix::from_fn(2, 3, |i, j| (i + j) % 2 == 0);
+        let mut buf = vec![];
+        g.write_text(&mut buf, |&b| if b { '#' } else { ' ' }).unwrap();
+        assert_eq!(buf, b"# #\n # \n");
+
+        let h = Matrix::read_text(&mut &buf[..], |c| match c {
+            '#' => Some(true),
+            ' ' => Some(false),
+            _ => None,
+        }).unwrap();
+        assert_eq!(h, g);
+    }
+
+    #[test]
+    fn text_empty_rows() {
+        /* With no rows there is no line to carry the width */
+        let g = Matrix::new_filled(0, 4, 0u8);
+        let mut buf = vec![];
+        g.write_text(&mut buf, |_| '.').unwrap();
+        assert!(buf.is_empty());
+
+        let h = Matrix::read_text(&mut &buf[..], |_| Some(0u8)).unwrap();
+        assert_eq!(h.get_dims(), (0, 0));
+    }
+
+    #[test]
+    fn text_rejects_bad_rows() {
+        let ragged = Matrix::read_text(&mut &b"..\n.\n"[..], |_| Some(()));
+        assert_eq!(ragged.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let bad = Matrix::read_text(&mut &b"..\n.x\n"[..], |c| (c == '.').then_some(()));
+        assert_eq!(bad.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut buf = header(1, 1);
+        buf[0] = b'X';
+        buf.extend_from_slice(&[0; 4]);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_bad_version() {
+        let mut buf = header(1, 1);
+        buf[4] = VERSION + 1;
+        buf.extend_from_slice(&[0; 4]);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_bad_order() {
+        let mut buf = header(1, 1);
+        buf[5] = 2;
+        buf.extend_from_slice(&[0; 4]);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_wrong_width() {
+        let mut buf = header(1, 1);
+        buf[6] = 8;
+        buf.extend_from_slice(&[0; 8]);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_overflowing_dims() {
+        let buf = header(u64::MAX, 2);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let mut buf = header(2, 2);
+        buf.extend_from_slice(&[0; 12]);
+        assert_eq!(read_err(&buf).kind(), io::ErrorKind::UnexpectedEof);
+
+        assert_eq!(read_err(&buf[..10]).kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_invalid_elements() {
+        let mut buf = header(1, 1);
+        buf[6] = 1;
+        buf.push(2);
+        let e = Matrix::<bool>::read_from(&mut &buf[..]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
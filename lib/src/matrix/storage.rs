@@ -0,0 +1,55 @@
+/* Where Matrix elements live. With the `allocator_api` feature (nightly)
+ * storage comes from any std Allocator; without it, Allocator and Global
+ * are stand-ins and the storage is a leaked Vec. */
+use std::ptr::NonNull;
+
+#[cfg(feature = "allocator_api")]
+pub use std::alloc::{Allocator, Global};
+
+#[cfg(not(feature = "allocator_api"))]
+pub trait Allocator {}
+
+#[cfg(not(feature = "allocator_api"))]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Global;
+
+#[cfg(not(feature = "allocator_api"))]
+impl Allocator for Global {}
+
+/* Uninitialised room for exactly len elements */
+#[cfg(feature = "allocator_api")]
+pub(super) fn allocate<T, A: Allocator>(len: usize, alloc: &A) -> NonNull<T> {
+    let layout = std::alloc::Layout::array::<T>(len).unwrap();
+    alloc.allocate(layout)
+         .unwrap_or_else(|_| std::alloc::handle_alloc_error(layout))
+         .cast::<T>()
+}
+
+/// # Safety
+/// ptr must come from allocate with the same len and allocator.
+#[cfg(feature = "allocator_api")]
+pub(super) unsafe fn deallocate<T, A: Allocator>(ptr: NonNull<T>, len: usize, alloc: &A) {
+    let layout = std::alloc::Layout::array::<T>(len).unwrap();
+    alloc.deallocate(ptr.cast::<u8>(), layout);
+}
+
+#[cfg(not(feature = "allocator_api"))]
+pub(super) fn allocate<T, A: Allocator>(len: usize, _alloc: &A) -> NonNull<T> {
+    use std::mem::MaybeUninit;
+
+    let mut v: Vec<MaybeUninit<T>> = Vec::with_capacity(len);
+    unsafe { v.set_len(len) };
+    /* Boxing the slice trims the capacity to len, so deallocate knows it */
+    let b = Box::into_raw(v.into_boxed_slice());
+    unsafe { NonNull::new_unchecked(b as *mut T) }
+}
+
+/// # Safety
+/// ptr must come from allocate with the same len and allocator.
+#[cfg(not(feature = "allocator_api"))]
+pub(super) unsafe fn deallocate<T, A: Allocator>(ptr: NonNull<T>, len: usize, _alloc: &A) {
+    use std::mem::MaybeUninit;
+
+    let s = std::ptr::slice_from_raw_parts_mut(ptr.as_ptr() as *mut MaybeUninit<T>, len);
+    drop(Box::from_raw(s));
+}
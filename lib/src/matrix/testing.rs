@@ -0,0 +1,48 @@
+/* Helpers shared by the matrix tests */
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::{Matrix, Order};
+
+/* Hands out non-Copy elements and counts how many of them are dropped, to
+ * catch elements that are leaked or dropped twice */
+#[derive(Default)]
+pub struct DropCounter(Rc<Cell<usize>>);
+
+pub struct Counted {
+    pub id: usize,
+    drops: Rc<Cell<usize>>,
+}
+
+impl DropCounter {
+    pub fn make(&self, id: usize) -> Counted {
+        Counted { id, drops: self.0.clone() }
+    }
+
+    pub fn drops(&self) -> usize {
+        self.0.get()
+    }
+}
+
+/* Clones count as separate elements, sharing the id */
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        Counted { id: self.id, drops: self.drops.clone() }
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+/* Cell (i, j) holds id 10i + j */
+pub fn counted(dc: &DropCounter, m: usize, n: usize, order: Order) -> Matrix<Counted> {
+    Matrix::from_fn_ordered(m, n, order, |i, j| dc.make(10*i + j))
+}
+
+/* The ids of g, row by row */
+pub fn ids(g: &Matrix<Counted>) -> Vec<Vec<usize>> {
+    g.rows().map(|row| row.map(|e| e.id).collect()).collect()
+}
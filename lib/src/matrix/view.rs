@@ -0,0 +1,270 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::ptr::NonNull;
+
+use super::{debug_grid, Allocator, Matrix};
+
+/* A rectangle of cells: (row, column) of the top-left corner, and
+ * (rows, columns) extent */
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Rect {
+    pub origin: (usize, usize),
+    pub dims: (usize, usize),
+}
+
+impl Rect {
+    pub fn new(origin: (usize, usize), dims: (usize, usize)) -> Self {
+        Rect { origin, dims }
+    }
+
+    /* Smallest rectangle covering both corners, inclusive */
+    pub fn from_corners((i0, j0): (usize, usize), (i1, j1): (usize, usize)) -> Self {
+        Rect { origin: (i0.min(i1), j0.min(j1)),
+               dims: (i0.abs_diff(i1) + 1, j0.abs_diff(j1) + 1) }
+    }
+
+    pub fn fits_in(&self, (m, n): (usize, usize)) -> bool {
+        let ((i, j), (h, w)) = (self.origin, self.dims);
+        i + h <= m && j + w <= n
+    }
+}
+
+/* Borrowed rectangle of a Matrix. Indices are relative to its top-left. */
+pub struct MatrixView<'a, T> {
+    ptr: NonNull<T>,
+    dims: (usize, usize),
+    strides: (usize, usize),
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct MatrixViewMut<'a, T> {
+    ptr: NonNull<T>,
+    dims: (usize, usize),
+    strides: (usize, usize),
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn view(&self, r: Rect) -> MatrixView<'_, T> {
+        let ptr = self.rect_ptr(r);
+        MatrixView { ptr, dims: r.dims, strides: self.strides(), _marker: PhantomData }
+    }
+
+    pub fn view_mut(&mut self, r: Rect) -> MatrixViewMut<'_, T> {
+        let ptr = self.rect_ptr(r);
+        MatrixViewMut { ptr, dims: r.dims, strides: self.strides(), _marker: PhantomData }
+    }
+
+    pub fn as_view(&self) -> MatrixView<'_, T> {
+        self.view(Rect::new((0, 0), self.dims))
+    }
+
+    /* Every h by w sub-rectangle, top-left corners in row-major order */
+    pub fn windows(&self, h: usize, w: usize) -> impl Iterator<Item=MatrixView<'_, T>> {
+        let (m, n) = self.dims;
+        let (rows, cols) = ((m + 1).saturating_sub(h), (n + 1).saturating_sub(w));
+        (0..rows).flat_map(move |i| {
+            (0..cols).map(move |j| self.view(Rect::new((i, j), (h, w))))
+        })
+    }
+
+    fn rect_ptr(&self, r: Rect) -> NonNull<T> {
+        let (m, n) = self.dims;
+        assert!(r.fits_in(self.dims), "{:?} out of bounds for {}x{} matrix", r, m, n);
+        let (h, w) = r.dims;
+        if h == 0 || w == 0 {
+            /* Never dereferenced */
+            return self.base_ptr;
+        }
+        unsafe { NonNull::new_unchecked(self.base_ptr.as_ptr().add(self.offset(r.origin))) }
+    }
+}
+
+macro_rules! view_common {
+    ($name:ident) => {
+        impl<'a, T> $name<'a, T> {
+            pub fn get_dims(&self) -> (usize, usize) {
+                self.dims
+            }
+
+            pub fn in_bounds(&self, (i, j): (usize, usize)) -> bool {
+                let (m, n) = self.dims;
+                i < m && j < n
+            }
+
+            pub fn get(&self, idx: (usize, usize)) -> Option<&T> {
+                if self.in_bounds(idx) {
+                    Some(unsafe { self.get_unchecked(idx) })
+                } else {
+                    None
+                }
+            }
+
+            /// # Safety
+            /// Caller must guarantee that `idx` is in bounds.
+            pub unsafe fn get_unchecked(&self, idx: (usize, usize)) -> &T {
+                &*self.ptr_at(idx)
+            }
+
+            /* Row-major order */
+            pub fn iter(&self) -> impl Iterator<Item=&T> {
+                self.indexed_iter().map(|(_, e)| e)
+            }
+
+            pub fn indexed_iter(&self) -> impl Iterator<Item=((usize, usize), &T)> {
+                let (m, n) = self.dims;
+                (0..m).flat_map(move |i| (0..n).map(move |j| (i, j)))
+                      .map(|idx| (idx, unsafe { self.get_unchecked(idx) }))
+            }
+
+            pub fn row(&self, i: usize) -> impl Iterator<Item=&T> {
+                let (m, n) = self.dims;
+                assert!(i < m, "row {} out of bounds for {}x{} view", i, m, n);
+                (0..n).map(move |j| unsafe { self.get_unchecked((i, j)) })
+            }
+
+            pub fn col(&self, j: usize) -> impl Iterator<Item=&T> {
+                let (m, n) = self.dims;
+                assert!(j < n, "column {} out of bounds for {}x{} view", j, m, n);
+                (0..m).map(move |i| unsafe { self.get_unchecked((i, j)) })
+            }
+
+            pub fn rows(&self) -> impl Iterator<Item=impl Iterator<Item=&T>> {
+                let (m, _) = self.dims;
+                (0..m).map(|i| self.row(i))
+            }
+
+            pub fn cols(&self) -> impl Iterator<Item=impl Iterator<Item=&T>> {
+                let (_, n) = self.dims;
+                (0..n).map(|j| self.col(j))
+            }
+
+            /* Sub-view, relative to this one */
+            pub fn view(&self, r: Rect) -> MatrixView<'_, T> {
+                MatrixView { ptr: self.rect_ptr(r),
+                             dims: r.dims,
+                             strides: self.strides,
+                             _marker: PhantomData }
+            }
+
+            pub fn to_matrix(&self) -> Matrix<T>
+                where T: Clone
+            {
+                let (m, n) = self.dims;
+                Matrix::from_fn(m, n, |i, j| self[(i, j)].clone())
+            }
+
+            fn ptr_at(&self, (i, j): (usize, usize)) -> *mut T {
+                let (si, sj) = self.strides;
+                unsafe { self.ptr.as_ptr().add(i*si + j*sj) }
+            }
+
+            fn rect_ptr(&self, r: Rect) -> NonNull<T> {
+                let (m, n) = self.dims;
+                assert!(r.fits_in(self.dims), "{:?} out of bounds for {}x{} view", r, m, n);
+                let (h, w) = r.dims;
+                if h == 0 || w == 0 {
+                    return self.ptr;
+                }
+                unsafe { NonNull::new_unchecked(self.ptr_at(r.origin)) }
+            }
+
+            fn check_bounds(&self, idx: (usize, usize)) {
+                if !self.in_bounds(idx) {
+                    let (m, n) = self.dims;
+                    panic!("index {:?} out of bounds for {}x{} view", idx, m, n);
+                }
+            }
+        }
+
+        impl<'a, T> Index<(usize, usize)> for $name<'a, T> {
+            type Output = T;
+            fn index(&self, idx: (usize, usize)) -> &Self::Output {
+                self.check_bounds(idx);
+                unsafe { self.get_unchecked(idx) }
+            }
+        }
+
+        impl<'a, T: fmt::Debug> fmt::Debug for $name<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                debug_grid(f, stringify!($name), self.dims, |idx| &self[idx])
+            }
+        }
+    };
+}
+
+view_common!(MatrixView);
+view_common!(MatrixViewMut);
+
+impl<T> Clone for MatrixView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for MatrixView<'_, T> {}
+
+/* Views behave like &T and &mut T respectively */
+unsafe impl<T: Sync> Send for MatrixView<'_, T> {}
+unsafe impl<T: Sync> Sync for MatrixView<'_, T> {}
+unsafe impl<T: Send> Send for MatrixViewMut<'_, T> {}
+unsafe impl<T: Sync> Sync for MatrixViewMut<'_, T> {}
+
+impl<'a, T> MatrixViewMut<'a, T> {
+    pub fn get_mut(&mut self, idx: (usize, usize)) -> Option<&mut T> {
+        if self.in_bounds(idx) {
+            Some(unsafe { self.get_unchecked_mut(idx) })
+        } else {
+            None
+        }
+    }
+
+    /// # Safety
+    /// Caller must guarantee that `idx` is in bounds.
+    pub unsafe fn get_unchecked_mut(&mut self, idx: (usize, usize)) -> &mut T {
+        &mut *self.ptr_at(idx)
+    }
+
+    /* Row-major order */
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=&mut T> {
+        let (m, n) = self.dims;
+        let (si, sj) = self.strides;
+        let ptr = self.ptr;
+        /* Every index is visited once, so the references never alias */
+        (0..m).flat_map(move |i| (0..n).map(move |j| (i, j)))
+              .map(move |(i, j)| unsafe { &mut *ptr.as_ptr().add(i*si + j*sj) })
+    }
+
+    pub fn fill(&mut self, e: T)
+        where T: Clone
+    {
+        for x in self.iter_mut() {
+            *x = e.clone();
+        }
+    }
+
+    /* Rows [0, i) and [i, m) as two disjoint views */
+    pub fn split_at_row(self, i: usize) -> (Self, Self) {
+        let (m, n) = self.dims;
+        assert!(i <= m, "row {} out of bounds for {}x{} view", i, m, n);
+        let bottom = self.rect_ptr(Rect::new((i, 0), (m - i, n)));
+        (MatrixViewMut { dims: (i, n), ..self },
+         MatrixViewMut { ptr: bottom, dims: (m - i, n), ..self })
+    }
+
+    /* Sub-view, relative to this one */
+    pub fn view_mut(&mut self, r: Rect) -> MatrixViewMut<'_, T> {
+        MatrixViewMut { ptr: self.rect_ptr(r),
+                        dims: r.dims,
+                        strides: self.strides,
+                        _marker: PhantomData }
+    }
+}
+
+impl<'a, T> IndexMut<(usize, usize)> for MatrixViewMut<'a, T> {
+    fn index_mut(&mut self, idx: (usize, usize)) -> &mut Self::Output {
+        self.check_bounds(idx);
+        unsafe { self.get_unchecked_mut(idx) }
+    }
+}
@@ -1,26 +1,308 @@
-use std::io::{BufReader, BufRead};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io::{self, BufReader, BufRead, Read};
 use std::fs::File;
-use std::str::FromStr;
 
-pub fn parse_non_empty_line(r: &mut BufReader<File>) -> Option<String> {
-    let line;
+use crate::matrix::Matrix;
 
-    /* Get non-empty payload line */
+mod captures;
+pub mod combinator;
+pub use captures::{line_format, parse_captures, parse_captures_within, Fields, FromCaptures, LineFormat};
+
+/* Reader that keeps track of which line it is on, for diagnostics */
+pub struct LineCounter<R> {
+    inner: R,
+    line: usize,
+    at_line_start: bool,
+}
+
+impl<R: BufRead> LineCounter<R> {
+    pub fn new(inner: R) -> Self {
+        LineCounter { inner, line: 0, at_line_start: true }
+    }
+
+    /* Number of the line last read from, counting from 1; 0 before any
+     * input has been read */
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl<R: BufRead> Read for LineCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        /* read_line consumes nothing at EOF, and refilling then would block
+         * for a second EOF on a terminal. Otherwise the bytes being consumed
+         * are already buffered, so fill_buf just returns them. */
+        if amt == 0 {
+            return;
+        }
+        if let Ok(buf) = self.inner.fill_buf() {
+            for &b in &buf[..amt.min(buf.len())] {
+                if self.at_line_start {
+                    self.line += 1;
+                    self.at_line_start = false;
+                }
+                self.at_line_start = b == b'\n';
+            }
+        }
+        self.inner.consume(amt)
+    }
+}
+
+/* Buffered reader over the file at path, or stdin if path is "-" */
+pub fn open_input(path: &str) -> io::Result<LineCounter<Box<dyn BufRead>>> {
+    let r: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    Ok(LineCounter::new(r))
+}
+
+/* Unexpected input at a position, or failure to read any */
+pub enum ParseError {
+    Io(io::Error),
+    Unexpected {
+        line: usize,
+        col: usize,
+        text: String,
+        expected: String,
+    },
+}
+
+impl ParseError {
+    /* Columns count characters from 1 */
+    pub fn unexpected<S, E>(line: usize, col: usize, text: S, expected: E) -> Self
+        where S: Into<String>,
+              E: Into<String>
+    {
+        ParseError::Unexpected { line, col, text: text.into(), expected: expected.into() }
+    }
+
+    /* part must be a slice of line, whose position gives the column */
+    pub fn within<E: Into<String>>(line_no: usize, line: &str, part: &str, expected: E) -> Self {
+        let offset = (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize)
+                                             .min(line.len());
+        let col = line[..offset].chars().count() + 1;
+        Self::unexpected(line_no, col, part, expected)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{e}"),
+            ParseError::Unexpected { line, col, text, expected } if text.is_empty() =>
+                write!(f, "line {line}, column {col}: expected {expected}, found end of line"),
+            ParseError::Unexpected { line, col, text, expected } =>
+                write!(f, "line {line}, column {col}: expected {expected}, found \"{text}\""),
+        }
+    }
+}
+
+/* Same as Display, so that a main returning Result<(), ParseError> reports
+ * the position rather than a struct dump */
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/* Next line with anything on it, trailing whitespace removed, or None at EOF */
+pub fn parse_non_empty_line<R: BufRead>(r: &mut R) -> Result<Option<String>, ParseError> {
     loop {
-        line = {
+        let mut l = String::new();
+        if r.read_line(&mut l)? == 0 {
+            return Ok(None);
+        }
+        let l = l.trim_end();
+        if !l.is_empty() {
+            return Ok(Some(l.to_string()));
+        }
+    }
+}
+
+/* Blocks of consecutive non-blank lines, with trailing whitespace removed.
+ * Any run of blank lines separates two blocks, so there are never empty
+ * blocks. */
+pub struct Records<R> {
+    r: R,
+    lines_read: usize,
+    start: usize,
+}
+
+pub fn records<R: BufRead>(r: R) -> Records<R> {
+    Records { r, lines_read: 0, start: 0 }
+}
+
+impl<R: BufRead> Records<R> {
+    /* Line number of the first line of the block last returned, counting
+     * from 1 where the records began */
+    pub fn line(&self) -> usize {
+        self.start
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Vec<String>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = vec![];
+        loop {
             let mut l = String::new();
-            let bytes = r.read_line(&mut l).unwrap();
-            let l_prefix = l.trim_end();
-            if bytes == 0 {
-                return None;
+            match self.r.read_line(&mut l) {
+                Ok(0) => break,
+                Ok(_) => self.lines_read += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let l = l.trim_end();
+            if l.is_empty() {
+                if block.is_empty() {
+                    continue;
+                }
+                break;
             }
-            if l_prefix.is_empty() {
+            if block.is_empty() {
+                self.start = self.lines_read;
+            }
+            block.push(l.to_string());
+        }
+        (!block.is_empty()).then_some(Ok(block))
+    }
+}
+
+/* An optional header block, and every line of the blocks after it with
+ * its line number */
+pub struct Sections {
+    pub header: Option<Vec<String>>,
+    pub body: Vec<(usize, String)>,
+}
+
+/* The first block is the header only if is_header accepts it, otherwise it
+ * is part of the body */
+pub fn sections<R, P>(r: R, is_header: P) -> Result<Sections, ParseError>
+    where R: BufRead,
+          P: FnOnce(&[String]) -> bool
+{
+    let mut blocks = records(r);
+    let mut is_header = Some(is_header);
+    let mut header = None;
+    let mut body = vec![];
+
+    while let Some(block) = blocks.next() {
+        let block = block?;
+        if is_header.take().is_some_and(|p| p(&block)) {
+            header = Some(block);
+            continue;
+        }
+        body.extend(block.into_iter()
+                         .enumerate()
+                         .map(|(k, line)| (blocks.line() + k, line)));
+    }
+    Ok(Sections { header, body })
+}
+
+/* Positions (row, column) of each marker character found in a grid */
+pub type Markers = HashMap<char, Vec<(usize, usize)>>;
+
+/* Read a block of equal-width lines into a matrix indexed by (row, column).
+ * Leading blank lines are skipped and the grid ends at the next blank line
+ * or EOF. Positions of any character in `markers` are reported before the
+ * character is mapped by f. Errors count lines from line_offset + 1, so
+ * pass the number of lines already read from the input, if any.
+ */
+pub fn parse_grid<R, T, F>(r: &mut R, line_offset: usize, markers: &str, mut f: F)
+    -> Result<(Matrix<T>, Markers), ParseError>
+    where R: BufRead,
+          F: FnMut(char) -> Option<T>
+{
+    let mut cells = vec![];
+    let mut found = Markers::new();
+    let (mut m, mut n) = (0, 0);
+
+    let mut line_no = line_offset;
+    loop {
+        let mut l = String::new();
+        if r.read_line(&mut l)? == 0 {
+            break;
+        }
+        line_no += 1;
+
+        let line = l.trim_end();
+        if line.is_empty() {
+            if m == 0 {
                 continue;
             }
-            String::from_str(l_prefix).unwrap()
-        };
-        break;
+            break;
+        }
+
+        let width = line.chars().count();
+        if m == 0 {
+            n = width;
+        } else if width != n {
+            return Err(ragged(line_no, line, n));
+        }
+
+        for (j, c) in line.chars().enumerate() {
+            if markers.contains(c) {
+                found.entry(c).or_default().push((m, j));
+            }
+            cells.push(f(c).ok_or_else(|| bad_cell(line_no, j, c))?);
+        }
+        m += 1;
     }
 
-    Some(line)
-}
\ No newline at end of file
+    Ok((Matrix::from_vec(m, n, cells), found))
+}
+
+/* Grid errors, shared with Matrix::read_text */
+pub(crate) fn ragged(line_no: usize, line: &str, width: usize) -> ParseError {
+    ParseError::unexpected(line_no, 1, line, format!("grid row of width {width}"))
+}
+
+pub(crate) fn bad_cell(line_no: usize, j: usize, c: char) -> ParseError {
+    ParseError::unexpected(line_no, j+1, c, "grid cell")
+}
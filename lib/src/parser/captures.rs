@@ -0,0 +1,175 @@
+use std::any;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+use super::ParseError;
+
+/* A regex built by line_format, which keeps the format it came from */
+pub struct LineFormat {
+    fmt: String,
+    re: Regex,
+}
+
+impl LineFormat {
+    /* As parse_captures, but a line that doesn't match is reported against
+     * the format rather than the regex built from it */
+    pub fn parse<T: FromCaptures>(&self, line_no: usize, line: &str) -> Result<T, ParseError> {
+        captures(&self.re, line_no, line, line, || format!("line of the form {:?}", self.fmt))
+    }
+}
+
+/* A line format with {name} for each field, such as
+ * "move {n} from {s} to {d}", as an anchored regex. Fields match lazily,
+ * so must be separated by literal text. {} is an unnamed field, and {{ and
+ * }} are literal braces.
+ */
+pub fn line_format(fmt: &str) -> LineFormat {
+    let mut pattern = String::from("^");
+    let mut literal = String::new();
+    let mut chars = fmt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => panic!("bad line format {:?}: unterminated field {{{}", fmt, name),
+                    }
+                }
+                pattern.push_str(&regex::escape(&literal));
+                literal.clear();
+                if name.is_empty() {
+                    pattern.push_str("(.*?)");
+                } else {
+                    pattern.push_str(&format!("(?P<{name}>.*?)"));
+                }
+            }
+            c => literal.push(c),
+        }
+    }
+    pattern.push_str(&regex::escape(&literal));
+    pattern.push('$');
+
+    let re = Regex::new(&pattern).unwrap_or_else(|e| panic!("bad line format {:?}: {}", fmt, e));
+    LineFormat { fmt: fmt.to_string(), re }
+}
+
+/* The capture groups of one match, with enough context to report which
+ * field failed to parse and where */
+pub struct Fields<'a> {
+    re: &'a Regex,
+    caps: Captures<'a>,
+    line_no: usize,
+    line: &'a str,
+}
+
+/* Types built from the fields of a match. Tuples take groups 1, 2, ... in
+ * order. */
+pub trait FromCaptures: Sized {
+    fn from_captures(f: &Fields<'_>) -> Result<Self, ParseError>;
+}
+
+impl<'a> Fields<'a> {
+    /* caps must come from matching re against line, or a slice of it */
+    pub fn new(re: &'a Regex, caps: Captures<'a>, line_no: usize, line: &'a str) -> Self {
+        Fields { re, caps, line_no, line }
+    }
+
+    pub fn parse<T: FromCaptures>(&self) -> Result<T, ParseError> {
+        T::from_captures(self)
+    }
+
+    /* Group i, which must have taken part in the match */
+    pub fn get<T: FromStr>(&self, i: usize) -> Result<T, ParseError> {
+        self.opt(i)?.ok_or_else(|| self.unexpected(format!("capture group {}", self.label(i))))
+    }
+
+    /* Group i, or None if it did not take part in the match */
+    pub fn opt<T: FromStr>(&self, i: usize) -> Result<Option<T>, ParseError> {
+        self.caps.get(i).map(|m| self.parse_field(m.as_str(), &self.label(i))).transpose()
+    }
+
+    pub fn name<T: FromStr>(&self, name: &str) -> Result<T, ParseError> {
+        self.name_opt(name)?.ok_or_else(|| self.unexpected(format!("capture group {name}")))
+    }
+
+    pub fn name_opt<T: FromStr>(&self, name: &str) -> Result<Option<T>, ParseError> {
+        self.caps.name(name).map(|m| self.parse_field(m.as_str(), name)).transpose()
+    }
+
+    /* An error against the whole line */
+    pub fn unexpected<E: Into<String>>(&self, expected: E) -> ParseError {
+        ParseError::unexpected(self.line_no, 1, self.line, expected)
+    }
+
+    fn parse_field<T: FromStr>(&self, s: &str, label: &str) -> Result<T, ParseError> {
+        s.parse().map_err(|_| {
+            let ty = any::type_name::<T>().rsplit("::").next().unwrap_or("value");
+            ParseError::within(self.line_no, self.line, s,
+                               format!("{ty} for capture group {label}"))
+        })
+    }
+
+    fn label(&self, i: usize) -> String {
+        match self.re.capture_names().nth(i).flatten() {
+            Some(name) => name.to_string(),
+            None => i.to_string(),
+        }
+    }
+}
+
+/* Match re against the whole of line and convert its groups */
+pub fn parse_captures<T>(re: &Regex, line_no: usize, line: &str) -> Result<T, ParseError>
+    where T: FromCaptures
+{
+    parse_captures_within(re, line_no, line, line)
+}
+
+/* As parse_captures, matching only part, a slice of line. Columns are
+ * still reported within line. */
+pub fn parse_captures_within<T>(re: &Regex, line_no: usize, line: &str, part: &str)
+    -> Result<T, ParseError>
+    where T: FromCaptures
+{
+    captures(re, line_no, line, part, || format!("text matching {}", re.as_str()))
+}
+
+fn captures<T, E>(re: &Regex, line_no: usize, line: &str, part: &str, expected: E)
+    -> Result<T, ParseError>
+    where T: FromCaptures,
+          E: FnOnce() -> String
+{
+    let caps = re.captures(part).ok_or_else(|| {
+        ParseError::within(line_no, line, part, expected())
+    })?;
+    Fields::new(re, caps, line_no, line).parse()
+}
+
+macro_rules! tuple_from_captures {
+    ($($t:ident $i:tt),*) => {
+        impl<$($t: FromStr),*> FromCaptures for ($($t,)*) {
+            fn from_captures(f: &Fields<'_>) -> Result<Self, ParseError> {
+                Ok(($(f.get::<$t>($i)?,)*))
+            }
+        }
+    };
+}
+
+tuple_from_captures!(A 1);
+tuple_from_captures!(A 1, B 2);
+tuple_from_captures!(A 1, B 2, C 3);
+tuple_from_captures!(A 1, B 2, C 3, D 4);
+tuple_from_captures!(A 1, B 2, C 3, D 4, E 5);
+tuple_from_captures!(A 1, B 2, C 3, D 4, E 5, F 6);
@@ -0,0 +1,283 @@
+/* Parser combinators over a single line of text.
+ *
+ * A parser takes the remaining input and returns what it parsed along with
+ * the input left over, or a Failure saying where and what it expected.
+ * Positions are slices of the original line, so they turn into columns once
+ * parse_all knows the whole line. Any Fn(&str) -> PResult is a parser, so
+ * grammars can also be written as plain recursive functions. Combinators
+ * return closures, so a parser used in several places can be passed by
+ * reference.
+ *
+ * Failures are normally backtracked over: alt tries the next alternative,
+ * and many and separated_list just stop. A committed failure is reported
+ * straight away instead. Elements after a separator are committed, as is
+ * anything inside cut, so that "[1,x]" is blamed on the x rather than
+ * quietly ending the list.
+ */
+use std::cell::OnceCell;
+use std::rc::{Rc, Weak};
+use std::str::FromStr;
+
+use super::ParseError;
+
+pub struct Failure<'a> {
+    /* Input from the point of failure */
+    pub rest: &'a str,
+    /* Bytes of rest that are the offending text */
+    pub len: usize,
+    pub expected: String,
+    pub committed: bool,
+}
+
+impl<'a> Failure<'a> {
+    pub fn new<E: Into<String>>(rest: &'a str, len: usize, expected: E) -> Self {
+        Failure { rest, len, expected: expected.into(), committed: false }
+    }
+
+    /* Failure at the start of rest, blaming its next character */
+    pub fn at<E: Into<String>>(rest: &'a str, expected: E) -> Self {
+        let len = rest.chars().next().map_or(0, char::len_utf8);
+        Self::new(rest, len, expected)
+    }
+
+    fn commit(self) -> Self {
+        Failure { committed: true, ..self }
+    }
+}
+
+pub type PResult<'a, T> = Result<(T, &'a str), Failure<'a>>;
+
+pub trait Parser<'a, T> {
+    fn parse(&self, input: &'a str) -> PResult<'a, T>;
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+    where F: Fn(&'a str) -> PResult<'a, T>
+{
+    fn parse(&self, input: &'a str) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/* Run p over the whole of line, which must be consumed entirely */
+pub fn parse_all<'a, T, P>(p: P, line_no: usize, line: &'a str) -> Result<T, ParseError>
+    where P: Parser<'a, T>
+{
+    let to_error = |f: Failure<'a>| {
+        ParseError::within(line_no, line, &f.rest[..f.len], f.expected)
+    };
+    match p.parse(line) {
+        Ok((t, "")) => Ok(t),
+        Ok((_, rest)) => Err(to_error(Failure::new(rest, rest.len(), "end of line"))),
+        Err(f) => Err(to_error(f)),
+    }
+}
+
+pub fn tag<'a>(t: &'a str) -> impl Fn(&'a str) -> PResult<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(t) {
+        Some(rest) => Ok((&input[..t.len()], rest)),
+        None => Err(Failure::at(input, format!("\"{t}\""))),
+    }
+}
+
+/* Optional minus sign and decimal digits, converted to T */
+pub fn integer<'a, T: FromStr>(input: &'a str) -> PResult<'a, T> {
+    let sign = usize::from(input.starts_with('-'));
+    let digits = input[sign..].bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return Err(Failure::at(input, "integer"));
+    }
+
+    let (text, rest) = input.split_at(sign + digits);
+    match text.parse() {
+        Ok(n) => Ok((n, rest)),
+        Err(_) => Err(Failure::new(input, text.len(), "integer in range")),
+    }
+}
+
+/* Any run of spaces and tabs, possibly empty */
+pub fn spaces(input: &str) -> PResult<'_, &str> {
+    let rest = input.trim_start_matches([' ', '\t']);
+    Ok((&input[..input.len() - rest.len()], rest))
+}
+
+pub fn map<'a, T, U, P, F>(p: P, f: F) -> impl Fn(&'a str) -> PResult<'a, U>
+    where P: Parser<'a, T>,
+          F: Fn(T) -> U
+{
+    move |input| p.parse(input).map(|(t, rest)| (f(t), rest))
+}
+
+pub fn pair<'a, T, U, P, Q>(p: P, q: Q) -> impl Fn(&'a str) -> PResult<'a, (T, U)>
+    where P: Parser<'a, T>,
+          Q: Parser<'a, U>
+{
+    move |input| {
+        let (t, rest) = p.parse(input)?;
+        let (u, rest) = q.parse(rest)?;
+        Ok(((t, u), rest))
+    }
+}
+
+pub fn preceded<'a, T, U, P, Q>(p: P, q: Q) -> impl Fn(&'a str) -> PResult<'a, U>
+    where P: Parser<'a, T>,
+          Q: Parser<'a, U>
+{
+    map(pair(p, q), |(_, u)| u)
+}
+
+pub fn terminated<'a, T, U, P, Q>(p: P, q: Q) -> impl Fn(&'a str) -> PResult<'a, T>
+    where P: Parser<'a, T>,
+          Q: Parser<'a, U>
+{
+    map(pair(p, q), |(t, _)| t)
+}
+
+/* p between open and close, keeping only what p parsed */
+pub fn delimited<'a, O, T, C, P, Q, R>(open: P, p: Q, close: R)
+    -> impl Fn(&'a str) -> PResult<'a, T>
+    where P: Parser<'a, O>,
+          Q: Parser<'a, T>,
+          R: Parser<'a, C>
+{
+    preceded(open, terminated(p, close))
+}
+
+/* Zero or more of p, separated by sep */
+pub fn separated_list<'a, T, S, P, Q>(p: P, sep: Q) -> impl Fn(&'a str) -> PResult<'a, Vec<T>>
+    where P: Parser<'a, T>,
+          Q: Parser<'a, S>
+{
+    move |input| {
+        let mut v = vec![];
+        let mut rest = match p.parse(input) {
+            Ok((t, rest)) => {
+                v.push(t);
+                rest
+            }
+            Err(f) if f.committed => return Err(f),
+            Err(_) => return Ok((v, input)),
+        };
+        while let Ok((_, after_sep)) = sep.parse(rest) {
+            let (t, after) = p.parse(after_sep).map_err(Failure::commit)?;
+            v.push(t);
+            rest = after;
+        }
+        Ok((v, rest))
+    }
+}
+
+/* Zero or more of p */
+pub fn many<'a, T, P>(p: P) -> impl Fn(&'a str) -> PResult<'a, Vec<T>>
+    where P: Parser<'a, T>
+{
+    move |input| {
+        let mut v = vec![];
+        let mut rest = input;
+        loop {
+            match p.parse(rest) {
+                /* Stop rather than loop forever on an empty match */
+                Ok((_, after)) if after.len() == rest.len() => return Ok((v, rest)),
+                Ok((t, after)) => {
+                    v.push(t);
+                    rest = after;
+                }
+                Err(f) if f.committed => return Err(f),
+                Err(_) => return Ok((v, rest)),
+            }
+        }
+    }
+}
+
+/* Failures of p are committed */
+pub fn cut<'a, T, P>(p: P) -> impl Fn(&'a str) -> PResult<'a, T>
+    where P: Parser<'a, T>
+{
+    move |input| p.parse(input).map_err(Failure::commit)
+}
+
+/* Alternatives for alt, as a tuple of parsers of the same type */
+pub trait Choice<'a, T> {
+    fn choose(&self, input: &'a str) -> PResult<'a, T>;
+}
+
+/* First of the alternatives to succeed, stopping at a committed failure.
+ * If none do, the failure that got furthest into the input is reported. */
+pub fn alt<'a, T, C>(choices: C) -> impl Fn(&'a str) -> PResult<'a, T>
+    where C: Choice<'a, T>
+{
+    move |input| choices.choose(input)
+}
+
+fn furthest<'a>(a: Failure<'a>, b: Failure<'a>) -> Failure<'a> {
+    if a.rest.len() < b.rest.len() {
+        a
+    } else if b.rest.len() < a.rest.len() {
+        b
+    } else {
+        Failure { expected: format!("{} or {}", a.expected, b.expected), ..a }
+    }
+}
+
+macro_rules! choice {
+    ($p0:ident $i0:tt $(, $p:ident $i:tt)*) => {
+        impl<'a, T, $p0 $(, $p)*> Choice<'a, T> for ($p0, $($p,)*)
+            where $p0: Parser<'a, T>
+                  $(, $p: Parser<'a, T>)*
+        {
+            fn choose(&self, input: &'a str) -> PResult<'a, T> {
+                let err = match self.$i0.parse(input) {
+                    Ok(r) => return Ok(r),
+                    Err(f) if f.committed => return Err(f),
+                    Err(f) => f,
+                };
+                $(
+                    let err = match self.$i.parse(input) {
+                        Ok(r) => return Ok(r),
+                        Err(f) if f.committed => return Err(f),
+                        Err(f) => furthest(err, f),
+                    };
+                )*
+                Err(err)
+            }
+        }
+    };
+}
+
+choice!(A 0, B 1);
+choice!(A 0, B 1, C 2);
+choice!(A 0, B 1, C 2, D 3);
+choice!(A 0, B 1, C 2, D 3, E 4);
+choice!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/* Stands in for the parser being defined inside recursive */
+pub struct Recursive<'a, T> {
+    cell: Weak<OnceCell<Box<dyn Parser<'a, T> + 'a>>>,
+}
+
+impl<'a, T> Clone for Recursive<'a, T> {
+    fn clone(&self) -> Self {
+        Recursive { cell: self.cell.clone() }
+    }
+}
+
+impl<'a, T> Parser<'a, T> for Recursive<'a, T> {
+    fn parse(&self, input: &'a str) -> PResult<'a, T> {
+        let cell = self.cell.upgrade().expect("recursive parser used after it was dropped");
+        let p = cell.get().expect("recursive parser used while being defined");
+        p.parse(input)
+    }
+}
+
+/* A parser that refers to itself: f gets a handle to the parser it is
+ * building, for use in nested positions */
+pub fn recursive<'a, T, P, F>(f: F) -> impl Fn(&'a str) -> PResult<'a, T>
+    where T: 'a,
+          P: Parser<'a, T> + 'a,
+          F: FnOnce(Recursive<'a, T>) -> P
+{
+    let cell: Rc<OnceCell<Box<dyn Parser<'a, T> + 'a>>> = Rc::new(OnceCell::new());
+    let p = f(Recursive { cell: Rc::downgrade(&cell) });
+    let _ = cell.set(Box::new(p));
+    move |input| cell.get().unwrap().parse(input)
+}
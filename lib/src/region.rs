@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use crate::matrix::{Allocator, BitMatrix, Matrix, ADJACENT, ORTHOGONAL};
+use crate::sparse::SparseGrid;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn deltas(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &ORTHOGONAL,
+            Connectivity::Eight => &ADJACENT,
+        }
+    }
+}
+
+/* One connected region. Its label is its position in the returned list. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Component<C> {
+    pub size: usize,
+    /* Inclusive (min, max) corners */
+    pub bounds: ((C, C), (C, C)),
+}
+
+pub type Idx<G> = (<G as Grid>::Coord, <G as Grid>::Coord);
+pub type Labels<G> = HashMap<Idx<G>, usize>;
+
+/* What the region algorithms need from a grid: cells addressed by a pair
+ * of coordinates, some of which may be missing. */
+pub trait Grid {
+    type Coord: Copy + Ord + Hash;
+    type Cell;
+
+    fn cell(&self, idx: (Self::Coord, Self::Coord)) -> Option<&Self::Cell>;
+
+    /* The index delta away from idx, if there is a cell there */
+    fn step_to(&self, idx: (Self::Coord, Self::Coord), delta: (isize, isize))
+        -> Option<(Self::Coord, Self::Coord)>;
+
+    fn cells(&self) -> Box<dyn Iterator<Item=(Idx<Self>, &Self::Cell)> + '_>;
+}
+
+impl<T, A: Allocator> Grid for Matrix<T, A> {
+    type Coord = usize;
+    type Cell = T;
+
+    fn cell(&self, idx: (usize, usize)) -> Option<&T> {
+        self.get(idx)
+    }
+
+    fn step_to(&self, idx: (usize, usize), delta: (isize, isize)) -> Option<(usize, usize)> {
+        self.step(idx, delta)
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item=((usize, usize), &T)> + '_> {
+        Box::new(self.indexed_iter())
+    }
+}
+
+impl<T> Grid for SparseGrid<T> {
+    type Coord = isize;
+    type Cell = T;
+
+    fn cell(&self, idx: (isize, isize)) -> Option<&T> {
+        self.get(idx)
+    }
+
+    fn step_to(&self, (i, j): (isize, isize), (di, dj): (isize, isize)) -> Option<(isize, isize)> {
+        let next = (i + di, j + dj);
+        self.contains(next).then_some(next)
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item=((isize, isize), &T)> + '_> {
+        Box::new(self.iter())
+    }
+}
+
+/* Where the region algorithms record their progress, so that each grid
+ * can keep visited cells and labels in its own layout */
+trait Visited<I> {
+    fn contains(&self, idx: &I) -> bool;
+    fn insert(&mut self, idx: I);
+}
+
+impl<I: Hash + Eq> Visited<I> for HashSet<I> {
+    fn contains(&self, idx: &I) -> bool {
+        HashSet::contains(self, idx)
+    }
+
+    fn insert(&mut self, idx: I) {
+        HashSet::insert(self, idx);
+    }
+}
+
+impl Visited<(usize, usize)> for BitMatrix {
+    fn contains(&self, &idx: &(usize, usize)) -> bool {
+        self[idx]
+    }
+
+    fn insert(&mut self, idx: (usize, usize)) {
+        self.set(idx, true);
+    }
+}
+
+trait LabelMap<I> {
+    fn is_labelled(&self, idx: &I) -> bool;
+    fn label(&mut self, idx: I, label: usize);
+}
+
+impl<I: Hash + Eq> LabelMap<I> for HashMap<I, usize> {
+    fn is_labelled(&self, idx: &I) -> bool {
+        self.contains_key(idx)
+    }
+
+    fn label(&mut self, idx: I, label: usize) {
+        self.insert(idx, label);
+    }
+}
+
+/* Unlabelled cells hold usize::MAX, which is never a label */
+impl LabelMap<(usize, usize)> for Matrix<usize> {
+    fn is_labelled(&self, &idx: &(usize, usize)) -> bool {
+        self[idx] != usize::MAX
+    }
+
+    fn label(&mut self, idx: (usize, usize), label: usize) {
+        self[idx] = label;
+    }
+}
+
+fn fill_into<G, P, V>(g: &G, start: Idx<G>, c: Connectivity, mut pred: P, visited: &mut V)
+    where G: Grid,
+          P: FnMut(&G::Cell) -> bool,
+          V: Visited<Idx<G>>
+{
+    let mut queue = VecDeque::new();
+
+    if g.cell(start).is_some_and(&mut pred) {
+        visited.insert(start);
+        queue.push_back(start);
+    }
+
+    while let Some(current) = queue.pop_front() {
+        for &d in c.deltas() {
+            if let Some(next) = g.step_to(current, d) {
+                if !visited.contains(&next) && pred(g.cell(next).unwrap()) {
+                    visited.insert(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+}
+
+/* Labels components starting from each of seeds in turn, which must cover
+ * every cell */
+fn label_into<G, I, S, L>(g: &G, seeds: I, c: Connectivity, mut same_region: S, labels: &mut L)
+    -> Vec<Component<G::Coord>>
+    where G: Grid,
+          I: IntoIterator<Item=Idx<G>>,
+          S: FnMut(&G::Cell, &G::Cell) -> bool,
+          L: LabelMap<Idx<G>>
+{
+    let mut components = vec![];
+    for seed in seeds {
+        if labels.is_labelled(&seed) {
+            continue;
+        }
+
+        let label = components.len();
+        let mut comp = Component { size: 0, bounds: (seed, seed) };
+        let mut queue = VecDeque::from([seed]);
+        labels.label(seed, label);
+
+        while let Some(current) = queue.pop_front() {
+            comp.size += 1;
+            let ((i0, j0), (i1, j1)) = comp.bounds;
+            let (i, j) = current;
+            comp.bounds = ((i0.min(i), j0.min(j)), (i1.max(i), j1.max(j)));
+
+            let here = g.cell(current).unwrap();
+            for &d in c.deltas() {
+                if let Some(next) = g.step_to(current, d) {
+                    if !labels.is_labelled(&next) && same_region(here, g.cell(next).unwrap()) {
+                        labels.label(next, label);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        components.push(comp);
+    }
+    components
+}
+
+/* Every cell reachable from start through cells satisfying pred, start
+ * included if it satisfies pred itself */
+pub fn flood_fill<G, P>(g: &G, start: Idx<G>, c: Connectivity, pred: P)
+    -> HashSet<Idx<G>>
+    where G: Grid,
+          P: FnMut(&G::Cell) -> bool
+{
+    let mut visited = HashSet::new();
+    fill_into(g, start, c, pred, &mut visited);
+    visited
+}
+
+/* Partition every cell into components, growing each one from a cell to
+ * any adjacent cell that same_region accepts. Only adjacent cells are
+ * compared, so if same_region isn't transitive a component can be a chain
+ * whose ends are not in the same region. Labels are handed out in
+ * row-major order of each component's first cell. */
+pub fn label_components<G, S>(g: &G, c: Connectivity, same_region: S)
+    -> (Labels<G>, Vec<Component<G::Coord>>)
+    where G: Grid,
+          S: FnMut(&G::Cell, &G::Cell) -> bool
+{
+    let mut order: Vec<_> = g.cells().map(|(idx, _)| idx).collect();
+    order.sort();
+
+    let mut labels = HashMap::new();
+    let components = label_into(g, order, c, same_region, &mut labels);
+    (labels, components)
+}
+
+impl<T, A: Allocator> Matrix<T, A> {
+    pub fn flood_fill<P>(&self, start: (usize, usize), c: Connectivity, pred: P) -> BitMatrix
+        where P: FnMut(&T) -> bool
+    {
+        let (m, n) = self.get_dims();
+        let mut visited = BitMatrix::new(m, n);
+        fill_into(self, start, c, pred, &mut visited);
+        visited
+    }
+
+    pub fn label_components<S>(&self, c: Connectivity, same_region: S)
+        -> (Matrix<usize>, Vec<Component<usize>>)
+        where S: FnMut(&T, &T) -> bool
+    {
+        let (m, n) = self.get_dims();
+        let mut labels = Matrix::new_filled(m, n, usize::MAX);
+        let seeds = (0..m).flat_map(|i| (0..n).map(move |j| (i, j)));
+        let components = label_into(self, seeds, c, same_region, &mut labels);
+        (labels, components)
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn flood_fill<P>(&self, start: (isize, isize), c: Connectivity, pred: P)
+        -> HashSet<(isize, isize)>
+        where P: FnMut(&T) -> bool
+    {
+        flood_fill(self, start, c, pred)
+    }
+
+    pub fn label_components<S>(&self, c: Connectivity, same_region: S)
+        -> (SparseGrid<usize>, Vec<Component<isize>>)
+        where S: FnMut(&T, &T) -> bool
+    {
+        let (labels, components) = label_components(self, c, same_region);
+        let mut s = SparseGrid::new();
+        for (idx, label) in labels {
+            s.insert(idx, label);
+        }
+        (s, components)
+    }
+}
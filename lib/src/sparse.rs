@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use crate::matrix::Matrix;
+
+/* Inclusive (min, max) corners */
+pub type BoundingBox = ((isize, isize), (isize, isize));
+
+/* Unbounded grid keyed by signed coordinates, which line up with a Matrix's
+ * (row, column) once an origin is chosen. Only occupied cells are stored. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SparseGrid<T> {
+    cells: HashMap<(isize, isize), T>,
+    bounds: Option<BoundingBox>,
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        SparseGrid { cells: HashMap::new(), bounds: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, p: (isize, isize)) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    pub fn get(&self, p: (isize, isize)) -> Option<&T> {
+        self.cells.get(&p)
+    }
+
+    pub fn get_mut(&mut self, p: (isize, isize)) -> Option<&mut T> {
+        self.cells.get_mut(&p)
+    }
+
+    pub fn get_or_insert_with<F>(&mut self, p: (isize, isize), f: F) -> &mut T
+        where F: FnOnce() -> T
+    {
+        self.grow(p);
+        self.cells.entry(p).or_insert_with(f)
+    }
+
+    pub fn insert(&mut self, p: (isize, isize), e: T) -> Option<T> {
+        self.grow(p);
+        self.cells.insert(p, e)
+    }
+
+    pub fn remove(&mut self, p: (isize, isize)) -> Option<T> {
+        let e = self.cells.remove(&p)?;
+
+        /* Only a cell on the edge of the box can shrink it */
+        if let Some(((i0, j0), (i1, j1))) = self.bounds {
+            let (i, j) = p;
+            if i == i0 || i == i1 || j == j0 || j == j1 {
+                self.bounds = None;
+                let keys: Vec<_> = self.cells.keys().copied().collect();
+                for q in keys {
+                    self.grow(q);
+                }
+            }
+        }
+        Some(e)
+    }
+
+    /* Arbitrary order */
+    pub fn iter(&self) -> impl Iterator<Item=((isize, isize), &T)> {
+        self.cells.iter().map(|(&p, e)| (p, e))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=((isize, isize), &mut T)> {
+        self.cells.iter_mut().map(|(&p, e)| (p, e))
+    }
+
+    /* None while the grid is empty */
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.bounds
+    }
+
+    fn grow(&mut self, (i, j): (isize, isize)) {
+        self.bounds = Some(match self.bounds {
+            None => ((i, j), (i, j)),
+            Some(((i0, j0), (i1, j1))) => ((i0.min(i), j0.min(j)),
+                                           (i1.max(i), j1.max(j))),
+        });
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> SparseGrid<T> {
+    /* Dense copy of the bounding box, with empty cells set to `fill`.
+     * Also returns the origin: the grid coordinate of matrix index (0, 0). */
+    pub fn to_matrix(&self, fill: T) -> (Matrix<T>, (isize, isize)) {
+        let ((i0, j0), (i1, j1)) = match self.bounds {
+            Some(b) => b,
+            None => return (Matrix::new_filled(0, 0, fill), (0, 0)),
+        };
+        let (m, n) = ((i1 - i0 + 1) as usize, (j1 - j0 + 1) as usize);
+        let g = Matrix::from_fn(m, n, |i, j| {
+            let p = (i0 + i as isize, j0 + j as isize);
+            self.cells.get(&p).unwrap_or(&fill).clone()
+        });
+        (g, (i0, j0))
+    }
+
+    /* Inverse of to_matrix, keeping only the cells that satisfy `keep` */
+    pub fn from_matrix<F>(g: &Matrix<T>, origin: (isize, isize), mut keep: F) -> Self
+        where F: FnMut(&T) -> bool
+    {
+        let mut s = SparseGrid::new();
+        for ((i, j), e) in g.indexed_iter() {
+            if keep(e) {
+                s.insert((origin.0 + i as isize, origin.1 + j as isize), e.clone());
+            }
+        }
+        s
+    }
+}
+
+impl<T> Index<(isize, isize)> for SparseGrid<T> {
+    type Output = T;
+    fn index(&self, p: (isize, isize)) -> &Self::Output {
+        self.get(p).unwrap_or_else(|| panic!("no cell at {:?} in sparse grid", p))
+    }
+}
+
+impl<T> IndexMut<(isize, isize)> for SparseGrid<T> {
+    fn index_mut(&mut self, p: (isize, isize)) -> &mut Self::Output {
+        self.get_mut(p).unwrap_or_else(|| panic!("no cell at {:?} in sparse grid", p))
+    }
+}
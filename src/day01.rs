@@ -1,35 +1,21 @@
-use std::io::{BufReader, BufRead, self};
-use std::fs::File;
-use std::str::FromStr;
-use std::error;
+use std::io::BufRead;
 use std::env;
 
+use aoc::parser::{open_input, records, ParseError};
 
-type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-fn parse(r: &mut BufReader<File>) -> Result<Vec<Vec<usize>>> {
+/* One block of calorie counts per elf */
+fn parse<R: BufRead>(r: &mut R) -> Result<Vec<Vec<usize>>, ParseError> {
     let mut elves = vec![];
 
-    'outer: loop {
-
-        let mut v: Vec<usize> = vec![];
-        loop {
-            let mut l = String::from_str("").unwrap();
-
-            let bytes = r.read_line(&mut l)?;
-            let line = l.trim_end();
-            println!("{line}");
-            if bytes == 0 {
-                break 'outer;                
-            }
-
-            if line.is_empty() {
-                break;
-            }
-
-            v.push(line.parse::<usize>()?);
+    let mut blocks = records(r);
+    while let Some(block) = blocks.next() {
+        let mut v = vec![];
+        for (k, line) in block?.iter().enumerate() {
+            v.push(line.parse::<usize>().map_err(|_| {
+                ParseError::unexpected(blocks.line() + k, 1, line, "calorie count")
+            })?);
         }
-
         elves.push(v);
     }
 
@@ -53,17 +39,16 @@ fn top_few(v: &Vec<usize>, n: usize) -> Vec<usize> {
     ret
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
         panic!("Incorrect number of args!")
     }
 
-    let f = File::open(&args[1])?;
+    let mut r = open_input(&args[1])?;
 
-    let v = parse(&mut BufReader::new(f))
-                .unwrap_or_else(|_| panic!("Unable to parse file"));
+    let v = parse(&mut r)?;
     
     let mut elf_sums: Vec<usize> = vec![];
 
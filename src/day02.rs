@@ -1,14 +1,10 @@
-use std::io::{BufReader, BufRead, self};
-use std::fs::File;
-use std::error;
+use std::io::BufRead;
 use std::env;
-use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-
-type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+use aoc::parser::{open_input, parse_captures, parse_non_empty_line, LineCounter, ParseError};
 
 
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -124,39 +120,12 @@ impl Game {
 }
 
 
-fn parse(r: &mut BufReader<File>) -> Result<Vec<Game>> {
+fn parse<R: BufRead>(r: &mut LineCounter<R>) -> Result<Vec<Game>, ParseError> {
     let mut v = vec![];
 
-    'outer: loop {
-        let line;
-
-        /* Get non-empty payload line */
-        loop {
-            line = {
-                let mut l = String::new();
-                let bytes = r.read_line(&mut l)?;
-                let l_prefix = l.trim_end();
-                if bytes == 0 {
-                    break 'outer;
-                }
-                if l_prefix.is_empty() {
-                    continue;
-                }
-                String::from_str(l_prefix)?
-            };
-            break;
-        }
-        
-        /* Regex parse the game */
-        let (them, us) = {
-            let caps = GAME_REGEX.captures(line.as_str()).unwrap();
-            (caps.get(1).unwrap()
-                        .as_str()
-                        .parse::<char>()?,
-             caps.get(2).unwrap()
-                        .as_str()
-                        .parse::<char>()?)
-        };
+    while let Some(line) = parse_non_empty_line(r)? {
+        /* The regex only admits valid codes, so from_code always succeeds */
+        let (them, us): (char, char) = parse_captures(&GAME_REGEX, r.line(), &line)?;
 
         v.push(Game::new(
                Play::from_code(us).unwrap(),
@@ -171,7 +140,7 @@ lazy_static! {
     static ref GAME_REGEX: Regex = Regex::new(r"([A-C])\s+([X-Z])").unwrap();
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
@@ -179,9 +148,8 @@ fn main() -> io::Result<()> {
     }
 
     let games = {
-        let f = File::open(&args[1])?;
-        parse(&mut BufReader::new(f))
-             .unwrap_or_else(|_| panic!("Unable to parse file"))
+        let mut r = open_input(&args[1])?;
+        parse(&mut r)?
     };
     
     /* Interpret right column as suggested response */
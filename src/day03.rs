@@ -1,20 +1,15 @@
 use std::collections::HashSet;
 use std::hash::Hash;
-use std::io::{BufReader, BufRead, self};
-use std::fs::File;
-use std::error;
+use std::io::BufRead;
 use std::env;
-use std::str::FromStr;
-use std::string::ParseError;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-
-type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+use aoc::parser::{open_input, parse_captures, parse_non_empty_line, LineCounter, ParseError};
 
 lazy_static! {
-    static ref RUCKSACK_REGEX: Regex = Regex::new(r"[a-zA-Z]+").unwrap();
+    static ref RUCKSACK_REGEX: Regex = Regex::new(r"^([a-zA-Z]+)$").unwrap();
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -122,63 +117,40 @@ impl Rucksack {
 }
 
 
-fn parse(r: &mut BufReader<File>) -> Result<Vec<Group>> {
+fn parse<R: BufRead>(r: &mut LineCounter<R>) -> Result<Vec<Group>, ParseError> {
 
     let mut v = vec![];
-    
-    let mut parse_group = || -> Option<Group> {
-        
-        let mut rs = vec![];
-        
-        'outer: for _ in 0..3 {
-            let line;
-
-            /* Get non-empty payload line */
-            loop {
-                line = {
-                    let mut l = String::new();
-                    let bytes = r.read_line(&mut l).unwrap();
-                    let l_prefix = l.trim_end();
-                    if bytes == 0 {
-                        return None;
-                    }
-                    if l_prefix.is_empty() {
-                        continue;
-                    }
-                    String::from_str(l_prefix).unwrap()
-                };
-                break;
-            }
+    let mut rs = vec![];
 
-            /* Regex parse the game */
-            let rucksack_str = RUCKSACK_REGEX.captures(&line)
-                                             .unwrap()
-                                             .get(0)
-                                             .unwrap()
-                                             .as_str();
+    while let Some(line) = parse_non_empty_line(r)? {
+        let (rucksack_str,): (String,) = parse_captures(&RUCKSACK_REGEX, r.line(), &line)?;
 
-            let l = rucksack_str.len();
-            if l % 2 != 0 {
-                panic!("Malformed rucksack")            
-            }
-            
-            rs.push(Rucksack::from_compontent_strings(
-                &rucksack_str[0..l/2],
-                &rucksack_str[l/2..l]
-            ))
+        let l = rucksack_str.len();
+        if l % 2 != 0 {
+            return Err(ParseError::unexpected(r.line(), 1, rucksack_str,
+                                              "rucksack with an even number of items"));
         }
 
-        Some(Group::from_rucksacks(rs[0].clone(), rs[1].clone(), rs[2].clone()))
-    };
+        rs.push(Rucksack::from_compontent_strings(
+            &rucksack_str[0..l/2],
+            &rucksack_str[l/2..l]
+        ));
+
+        if rs.len() == 3 {
+            v.push(Group::from_rucksacks(rs[0].clone(), rs[1].clone(), rs[2].clone()));
+            rs.clear();
+        }
+    }
 
-    while let Some(g) = parse_group() {
-        v.push(g)
+    if !rs.is_empty() {
+        return Err(ParseError::unexpected(r.line() + 1, 1, "end of file",
+                                          "three rucksacks per group"));
     }
 
     Ok(v)
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
@@ -186,9 +158,8 @@ fn main() -> io::Result<()> {
     }
     
     let groups = {
-        let f = File::open(&args[1])?;
-        parse(&mut BufReader::new(f))
-             .unwrap_or_else(|_| panic!("Unable to parse file"))
+        let mut r = open_input(&args[1])?;
+        parse(&mut r)?
     };
     
     let priority_sum = groups.iter()
@@ -1,11 +1,9 @@
-use std::io::{BufReader, self};
-use std::fs::File;
 use std::env;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{open_input, parse_captures, parse_non_empty_line, ParseError};
 
 lazy_static! {
     static ref ASSIGNMENT_REGEX: Regex = Regex::new(r"([0-9]+)-([0-9]+),([0-9]+)-([0-9]+)").unwrap();
@@ -27,29 +25,22 @@ fn range_overlap(ra: &(usize, usize), rb: &(usize, usize)) -> bool {
                          || left_overlap(rb, ra)
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = File::open(&args[1])?;
-        BufReader::new(f)
-    };
+    let mut r = open_input(&args[1])?;
 
     let mut line_no = 0;
     let mut subset_pairs = vec![];
     let mut overlap_pairs = vec![];
-    while let Some(line) = parse_non_empty_line(&mut r) {
+    while let Some(line) = parse_non_empty_line(&mut r)? {
 
-        let captures = ASSIGNMENT_REGEX.captures(&line).unwrap();
-
-        let ra = (captures.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-                  captures.get(2).unwrap().as_str().parse::<usize>().unwrap());
-        let rb = (captures.get(3).unwrap().as_str().parse::<usize>().unwrap(),
-                  captures.get(4).unwrap().as_str().parse::<usize>().unwrap());
+        let (a0, a1, b0, b1) = parse_captures(&ASSIGNMENT_REGEX, r.line(), &line)?;
+        let (ra, rb) = ((a0, a1), (b0, b1));
 
         if range_subset(&ra, &rb) || range_subset(&rb, &ra) {
             subset_pairs.push(line_no);
@@ -1,15 +1,10 @@
-use std::io::{BufReader, self};
-use std::fs::File;
 use std::env;
 
 use lazy_static::lazy_static;
-use regex::Regex;
-
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{line_format, open_input, sections, LineFormat, ParseError};
 
 lazy_static! {
-    static ref DIRECTIVE_REGEX: Regex = Regex::new(r"move ([0-9]+) from ([0-9]) to ([0-9])")
-                                              .unwrap();
+    static ref DIRECTIVE_FORMAT: LineFormat = line_format("move {n} from {s} to {d}");
     static ref STACKS_INIT: Vec<Vec<char>> = vec![
     vec!['W','B','D','N','C','F','J'],
     vec!['P', 'Z', 'V', 'Q', 'L', 'S', 'T'],
@@ -45,29 +40,44 @@ lazy_static! {
 //     }
 // }
 
-fn main() -> io::Result<()> {
+/* Crates sit in every fourth column, above a row of stack numbers */
+fn parse_stacks(drawing: &[String]) -> Vec<Vec<char>> {
+    let (numbers, rows) = drawing.split_last().unwrap();
+    let mut stacks = vec![vec![]; numbers.split_whitespace().count()];
+    for row in rows.iter().rev() {
+        for (stack, c) in stacks.iter_mut().zip(row.chars().skip(1).step_by(4)) {
+            if c != ' ' {
+                stack.push(c);
+            }
+        }
+    }
+    stacks
+}
+
+fn main() -> Result<(), ParseError> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = File::open(&args[1])?;
-        BufReader::new(f)
-    };
+    let mut r = open_input(&args[1])?;
     
+    /* An optional drawing of the stacks, then blocks of directives */
+    let sections = sections(&mut r, |block| !block[0].starts_with("move"))?;
+    let stacks = match sections.header {
+        Some(drawing) => parse_stacks(&drawing),
+        None => STACKS_INIT.clone(),
+    };
+    let directives = sections.body;
+
     // Clone our stacks
-    let mut stacks_p1 = STACKS_INIT.clone();
-    let mut stacks_p2 = STACKS_INIT.clone();
+    let mut stacks_p1 = stacks.clone();
+    let mut stacks_p2 = stacks;
 
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        let (n, s, d) = {
-            let captures = DIRECTIVE_REGEX.captures(&line).unwrap();
-            (captures.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-             captures.get(2).unwrap().as_str().parse::<usize>().unwrap(),
-             captures.get(3).unwrap().as_str().parse::<usize>().unwrap())
-        };
+    for (line_no, line) in directives {
+        let (n, s, d): (usize, usize, usize)
+            = DIRECTIVE_FORMAT.parse(line_no, &line)?;
         
         /* Part One manipulations */
         let mut v = vec![];
@@ -1,12 +1,10 @@
 
 use std::collections::VecDeque;
-use std::io::{BufReader, self};
-use std::fs::File;
 use std::env;
 
 use lazy_static::lazy_static;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{open_input, parse_non_empty_line, ParseError};
 
 lazy_static! {
     static ref PACKET_LENGTH: usize = 14;
@@ -24,7 +22,7 @@ fn check_unique(v: &VecDeque<char>) -> bool {
     true
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
     
     let args: Vec<String> = env::args().collect();
 
@@ -32,12 +30,11 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = File::open(&args[1])?;
-        BufReader::new(f)
-    };   
+    let mut r = open_input(&args[1])?;
     
-    let line = parse_non_empty_line(&mut r).unwrap();
+    let line = parse_non_empty_line(&mut r)?.ok_or_else(|| {
+        ParseError::unexpected(r.line() + 1, 1, "", "datastream")
+    })?;
     
     let marker = {
         let mut view = VecDeque::new();
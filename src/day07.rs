@@ -1,13 +1,10 @@
-#![feature(let_chains)]
 use std::env;
-use std::io::{self, BufReader};
-use std::fs;
 use std::ptr::NonNull;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{open_input, parse_captures, parse_non_empty_line, Fields, FromCaptures, ParseError};
 
 lazy_static! {
     static ref CMD_REGEX: Regex
@@ -25,6 +22,22 @@ enum LineInfo {
     DirEntry(String)
 }
 
+impl FromCaptures for LineInfo {
+    fn from_captures(f: &Fields<'_>) -> Result<Self, ParseError> {
+        if let Some(cd) = f.opt(1)? {
+            Ok(LineInfo::Cd(cd))
+        } else if f.opt::<String>(2)?.is_some() {
+            Ok(LineInfo::Ls)
+        } else if let (Some(size), Some(file)) = (f.opt(3)?, f.opt(4)?) {
+            Ok(LineInfo::Stat(size, file))
+        } else if let Some(dir) = f.opt(5)? {
+            Ok(LineInfo::DirEntry(dir))
+        } else {
+            Err(f.unexpected("command or listing"))
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Directory {
     dirname: String,
@@ -125,7 +138,7 @@ impl Inode {
     }
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
 
@@ -133,32 +146,13 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
+    let mut r = open_input(&args[1])?;
 
     let mut v = vec![];
 
     /* parse symbols */
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        let captures = CMD_REGEX.captures(&line).unwrap();
-
-        let li = if let Some(cd) = captures.get(1) {
-            LineInfo::Cd(cd.as_str().to_string())
-        } else if let Some(_) = captures.get(2) {
-            LineInfo::Ls
-        } else if let Some(size) = captures.get(3)
-               && let Some(file) = captures.get(4) {
-            LineInfo::Stat(size.as_str().parse::<usize>().unwrap(),
-                           file.as_str().to_string())
-        } else if let Some(dir) = captures.get(5) {
-            LineInfo::DirEntry(dir.as_str().to_string())
-        } else {
-            panic!("File didn't parse")
-        };
-
-        v.push(li);
+    while let Some(line) = parse_non_empty_line(&mut r)? {
+        v.push(parse_captures::<LineInfo>(&CMD_REGEX, r.line(), &line)?);
     }
 
     let mut root = Inode::root();
@@ -1,10 +1,8 @@
 use std::collections::HashSet;
 use std::env;
-use std::fs;
-use std::io;
-use std::io::BufReader;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::geom::Point2;
+use aoc::parser::{open_input, parse_non_empty_line, ParseError};
 
 #[derive(Copy, Clone,Debug)]
 enum Direction {
@@ -15,25 +13,16 @@ enum Direction {
 }
 
 impl Direction {
-    fn move_(&self, (x, y): &mut (isize, isize)) {
+    fn delta(&self) -> Point2<isize> {
         match self {
-            Direction::Left => *x -= 1,
-            Direction::Right => *x += 1,
-            Direction::Down => *y -= 1,
-            Direction::Up => *y += 1
-        };
+            Direction::Left => Point2::new(-1, 0),
+            Direction::Right => Point2::new(1, 0),
+            Direction::Down => Point2::new(0, -1),
+            Direction::Up => Point2::new(0, 1)
+        }
     }
 }
 
-/* Provides a metric on the rope-norm space, defined as the infima of
- * 8-directional movements.
- */
-fn rope_norm(d: (isize, isize)) -> usize {
-    let (x,y) = (d.0.abs() as usize,
-                 d.1.abs() as usize);
-    x.max(y)
-}
-
 fn simulate_rope(num_links: usize, moves: &Vec<Direction>) -> usize {
 
     if num_links < 2 {
@@ -41,40 +30,29 @@ fn simulate_rope(num_links: usize, moves: &Vec<Direction>) -> usize {
     }
 
     let mut links = vec![];
-    links.resize(num_links, (0 as isize, 0 as isize));
+    links.resize(num_links, Point2::<isize>::new(0, 0));
 
     let mut tail_positions = HashSet::new();
     tail_positions.insert(*links.last().unwrap());
 
     for m in moves {
-        m.move_(&mut links[0]);
+        links[0] += m.delta();
 
         for i in 1 .. num_links {
 
-            let rel_head = links[i-1];
-            let rel_tail = &mut links[i];
+            /* The rope-norm is the Chebyshev distance: the infima of
+             * 8-directional movements. */
+            let diff = links[i-1] - links[i];
 
-            let diff = (rel_head.0 - rel_tail.0,
-                        rel_head.1 - rel_tail.1);
-
-            if rope_norm(diff) > 2 {
+            if diff.chebyshev() > 2 {
                 panic!("Difference is too large");
             }
 
-            if rope_norm(diff) < 2 {
+            if diff.chebyshev() < 2 {
                 continue;
             }
 
-            if diff.0 < 0 {
-                Direction::Left.move_(rel_tail);
-            } else if diff.0 > 0 {
-                Direction::Right.move_(rel_tail);
-            }
-            if diff.1 < 0 {
-                Direction::Down.move_(rel_tail);
-            } else if diff.1 > 0 {
-                Direction::Up.move_(rel_tail);
-            }
+            links[i] += diff.signum();
         }
 
         tail_positions.insert(*links.last().unwrap());
@@ -83,7 +61,7 @@ fn simulate_rope(num_links: usize, moves: &Vec<Direction>) -> usize {
     tail_positions.len()
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
 
@@ -91,23 +69,22 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
+    let mut r = open_input(&args[1])?;
 
     let mut head_directions = vec![];
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        let parts: Vec<&str> = line.splitn(2, ' ').collect();
-        let (d, n) = (parts[0].to_string(),
-                      parts[1].to_string().parse::<usize>().unwrap());
-
-        let d = match d.as_str() {
+    while let Some(line) = parse_non_empty_line(&mut r)? {
+        let (d, n) = line.split_once(' ').ok_or_else(|| {
+            ParseError::unexpected(r.line(), 1, &line, "direction and distance")
+        })?;
+        let n = n.parse::<usize>()
+                 .map_err(|_| ParseError::within(r.line(), &line, n, "distance"))?;
+
+        let d = match d {
             "L" => Direction::Left,
             "R" => Direction::Right,
             "D" => Direction::Down,
             "U" => Direction::Up,
-             _  => panic!("Invalid direction")
+             _  => return Err(ParseError::within(r.line(), &line, d, "one of L, R, D, U"))
         };
 
         for _ in 0 .. n {
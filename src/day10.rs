@@ -1,14 +1,11 @@
 #![allow(non_snake_case)]
 use std::env;
-use std::fs;
-use std::io;
-use std::io::BufReader;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use aoc::parser::parse_non_empty_line;
-use aoc::matrix::Matrix;
+use aoc::parser::{open_input, parse_captures, parse_non_empty_line, Fields, FromCaptures, ParseError};
+use aoc::matrix::BitMatrix;
 
 
 lazy_static! {
@@ -80,7 +77,16 @@ enum Op {
     Addx(isize)
 }
 
-fn main() -> io::Result<()> {
+impl FromCaptures for Op {
+    fn from_captures(f: &Fields<'_>) -> Result<Self, ParseError> {
+        match f.opt(3)? {
+            Some(imm) => Ok(Op::Addx(imm)),
+            None      => Ok(Op::Noop)
+        }
+    }
+}
+
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
 
@@ -88,24 +94,11 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
+    let mut r = open_input(&args[1])?;
 
     let mut ops = vec![];
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        let captures = OP_REGEX.captures(&line).unwrap();
-
-        if let Some(_noop) = captures.get(1) {
-            ops.push(Op::Noop);
-        } else if let Some(_addx) = captures.get(2) {
-            let imm = captures.get(3).unwrap()
-                                     .as_str()
-                                     .parse::<isize>()
-                                     .unwrap();
-            ops.push(Op::Addx(imm));
-        }
+    while let Some(line) = parse_non_empty_line(&mut r)? {
+        ops.push(parse_captures(&OP_REGEX, r.line(), &line)?);
     }
 
     /* Part one no pipeline. */
@@ -128,7 +121,7 @@ fn main() -> io::Result<()> {
 
     println!("Part one: signal strength: {}", signal_strengths.iter().sum::<isize>());
 
-    let mut crt = Matrix::new(40, 6);
+    let mut crt = BitMatrix::new(6, 40);
 
     let mut cpu = Core::new(*REG_X_START);
     let mut sprite_range = {
@@ -144,7 +137,7 @@ fn main() -> io::Result<()> {
             let x = (c-1) % 40;
             let y = (c-1) / 40;
 
-            crt[(x,y)] = sprite_range.contains(&x);
+            crt.set((y,x), sprite_range.contains(&x));
 
             !cpu.dispatch()
         } {}
@@ -156,12 +149,7 @@ fn main() -> io::Result<()> {
     }
 
     println!("Part two:");
-    for y in 0 .. 6 {
-        for x in 0 .. 40 {
-            print!("{}", if crt[(x,y)] { '#' } else { '.' });
-        }
-        println!();
-    }
+    print!("{}", crt.render(|lit| if lit { '#' } else { '.' }));
 
     Ok(())
 }
\ No newline at end of file
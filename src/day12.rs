@@ -1,17 +1,14 @@
 #![feature(let_chains)]
 use std::collections::VecDeque;
 use std::env;
-use std::fs;
-use std::io;
-use std::io::BufReader;
 use std::mem;
 
 use priority_queue::PriorityQueue;
 
-use aoc::parser::parse_non_empty_line;
-use aoc::matrix::Matrix;
+use aoc::parser::{open_input, parse_grid, ParseError};
+use aoc::matrix::{BitMatrix, Matrix};
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
 
@@ -19,67 +16,35 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
-
-    let mut lines = vec![];
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        lines.push(line);
-    }
+    let mut r = open_input(&args[1])?;
 
-    let mut topo = Matrix::new(lines.len(),
-                               lines[1].len());
-    let mut start = (0,0);
-    let mut end = (0,0);
+    let (topo, markers) = parse_grid(&mut r, 0, "SE", |c| {
+        match c {
+            'S'        => Some(1),
+            'E'        => Some(26),
+            'a'..='z'  => Some(c as u8 - b'a' + 1),
+             _         => None
+        }
+    })?;
+    let marker = |c: char, what: &str| {
+        markers.get(&c)
+               .and_then(|v| v.first().copied())
+               .ok_or_else(|| ParseError::unexpected(r.line(), 1, "end of grid",
+                                                     format!("{what} marker '{c}'")))
+    };
+    let start = marker('S', "start")?;
+    let end = marker('E', "end")?;
 
     let (m, n) = topo.get_dims();
-    for i in 0 .. m {
-        let line = &lines[i];
-        for j in 0 .. n {
-            let c = line.get(j..=j).unwrap().as_bytes()[0];
-            topo[(i,j)] = match c as char {
-                'S' => {
-                    start = (i,j);
-                    1
-                },
-                'E' => {
-                    end = (i,j);
-                    26
-                },
-                 _  => c - ('a' as u8) + 1
-            };
-        }
-    }
 
     /* breadth first-search */
-    let mut dist = Matrix::new(m, n);
-    dist.fill(0);
-    let mut scheduled = Matrix::new(m, n);
-    scheduled.fill(false);
+    let mut dist = Matrix::new_filled(m, n, 0);
+    let mut scheduled = BitMatrix::new(m, n);
     let mut visit_queue = VecDeque::new();
 
     let mut reached = 0;
     visit_queue.push_front(start);
-    scheduled[start] = true;
-
-    let neighbours = |(i,j)| {
-        let mut v = vec![];
-        if i > 0 {
-            v.push((i-1,j));
-        }
-        if i < m-1 {
-            v.push((i+1,j));
-        }
-        if j > 0 {
-            v.push((i,j-1));
-        }
-        if j < n-1 {
-            v.push((i,j+1));
-        }
-        v
-    };
+    scheduled.set(start, true);
 
     for d in 0 .. {
 
@@ -95,11 +60,11 @@ fn main() -> io::Result<()> {
         while let Some(current) = todo.pop_back() {
             dist[current] = d;
             reached += 1;
-            for next in neighbours(current) {
+            for next in topo.neighbours4(current) {
                 if !scheduled[next]
                  && topo[current] + 1 >= topo[next] {
                     visit_queue.push_back(next);
-                    scheduled[next] = true;
+                    scheduled.set(next, true);
                 }
             }
         }
@@ -114,26 +79,20 @@ fn main() -> io::Result<()> {
     println!("Part one: fewest steps is {}", dist[end]);
 
 
-    let mut low_nodes = vec![];
+    let low_nodes: Vec<(usize, usize)> = topo.indexed_iter()
+                                             .filter(|(_, &h)| h == 1)
+                                             .map(|(idx, _)| idx)
+                                             .collect();
 
     /* Initiate Dijkstra (in reverse) from end node */
-    let mut dist = Matrix::new(m, n);
-    dist.fill(usize::max_value());
+    let mut dist = Matrix::new_filled(m, n, usize::MAX);
     dist[end] = 0;
 
-    let mut scheduled = Matrix::new(m, n);
-    scheduled.fill(true);
+    let mut scheduled = BitMatrix::new_filled(m, n, true);
 
     let mut queue = PriorityQueue::new();
-    for j in 0..n {
-        for i in 0..m {
-            let idx = (i,j);
-            if topo[idx] == 1 {
-                low_nodes.push(idx);
-            }
-            queue.push(idx,
-                       usize::max_value() - dist[idx]);
-        }
+    for (idx, d) in dist.indexed_iter() {
+        queue.push(idx, usize::MAX - d);
     }
 
     while let Some((current, p)) = queue.pop() {
@@ -143,8 +102,8 @@ fn main() -> io::Result<()> {
             break;
         }
 
-        scheduled[current] = false;
-        for prev in neighbours(current) {
+        scheduled.set(current, false);
+        for prev in topo.neighbours4(current) {
             /* check that prev can reach current */
             if  scheduled[prev]
              && topo[prev] + 1 >= topo[current] {
@@ -1,11 +1,10 @@
 #![allow(non_snake_case)]
 
 use std::env;
-use std::fs;
-use std::io;
-use std::io::BufReader;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{open_input, records, ParseError};
+use aoc::parser::combinator::{alt, delimited, integer, map, parse_all, recursive,
+                              separated_list, spaces, tag, PResult};
 
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -64,70 +63,21 @@ impl Ord for Packet {
     }
 }
 
-fn parse_packet(line: &String) -> Packet {
-
-    let s: Vec<char> = line.as_str().chars().collect();
-    let mut idx = 0;
-
-    fn recurse(idx: &mut usize, s: &Vec<char>) -> Packet {
-        assert!(s[*idx] == '[');
-        *idx += 1; /* consume opening bracket */
-
-        let mut subs = vec![];
-        while *idx != s.len() {
-            let c = s[*idx];
-            match c {
-                '0'..='9' => {
-                    let mut consecutive_numbers = vec![];
-                    while {
-                        let num_start = *idx;
-                        loop {
-                            let numeric = ('0'..='9').contains(&s[*idx]);
-                            if !numeric {
-                                break;
-                            }
-                            *idx += 1;
-                        }
-                        consecutive_numbers.push(String::from_iter(s[num_start..*idx].iter())
-                                                 .parse::<usize>().unwrap());
-
-                        match s[*idx] {
-                            ',' => {
-                                *idx += 1; /* assume no whitespace */
-                            }
-                             _  => {}
-                        }
-
-                        match s[*idx] {
-                            '0'..='9' => true,
-                            _         => false
-                        }
-                    } {}
-                    subs.append(&mut consecutive_numbers.iter().map(|n| Packet::Entry(*n)).collect());
-                },
-                '[' => {
-                    subs.push(recurse(idx, s));
-                },
-                ']' => {
-                    *idx += 1;
-                    return Packet::Sub(subs);
-                },
-                ',' => {
-                    *idx += 1;
-                },
-                 _  => {
-                    panic!("Invalid character '{c}' at idx {}", *idx);
-                }
-            }
-        }
-
-        panic!("Failed to parse subpacket");
-    }
+/* Punctuation, with any spaces around it */
+fn token<'a>(t: &'a str) -> impl Fn(&'a str) -> PResult<'a, &'a str> {
+    delimited(spaces, tag(t), spaces)
+}
 
-    recurse(&mut idx, &s)
+fn parse_packet(line_no: usize, line: &str) -> Result<Packet, ParseError> {
+    let packet = recursive(|packet| {
+        let element = alt((map(integer, Packet::Entry), packet));
+        map(delimited(token("["), separated_list(element, token(",")), token("]")),
+            Packet::Sub)
+    });
+    parse_all(packet, line_no, line)
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
 
@@ -135,22 +85,18 @@ fn main() -> io::Result<()> {
         panic!("Incorrect number of args!")
     }
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
-
-    let mut v = vec![];
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        v.push(line);
-    }
+    let mut r = open_input(&args[1])?;
 
     let mut packet_pairs = vec![];
-    for p in 0 .. v.len() / 2 {
+    let mut blocks = records(&mut r);
+    while let Some(block) = blocks.next() {
+        let block = block?;
+        if block.len() != 2 {
+            return Err(ParseError::unexpected(blocks.line(), 1, &block[0], "pair of packets"));
+        }
         let mut pair = vec![];
-        for e in 0 .. 2 {
-            let packet_txt = &v[p*2 + e];
-            pair.push(parse_packet(packet_txt));
+        for (k, line) in block.iter().enumerate() {
+            pair.push(parse_packet(blocks.line() + k, line)?);
         }
         packet_pairs.push(pair);
     }
@@ -1,77 +1,36 @@
 #![feature(let_chains)]
 
 use std::env;
-use std::fs;
-use std::io;
-use std::io::BufRead;
-use std::io::BufReader;
-use std::ops::Index;
-use std::ops::IndexMut;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use aoc::geom::Point2;
 use aoc::matrix::Matrix;
-use aoc::parser::parse_non_empty_line;
+use aoc::parser::{open_input, parse_captures_within, parse_non_empty_line, ParseError};
 
 lazy_static! {
-    static ref POINT_REGEX: Regex = Regex::new(r"(\-?[0-9]+),(\-?[0-9])+")
+    static ref POINT_REGEX: Regex = Regex::new(r"(\-?[0-9]+),(\-?[0-9]+)")
                           .unwrap();
 }
 
-#[derive(Copy, Clone)]
-struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl Point {
-    fn straight_line(&self, p: &Self) -> Option<Box<dyn Iterator<Item=Self>>> {
-        let q = self;
-
-        if (q.x != p.x) && (q.y != p.y) {
-            return None;
-        }
+type Point = Point2<i64>;
 
-        if q.x == p.x {
-            let x = q.x;
-            let a = p.y.min(q.y);
-            let b = p.y.max(q.y);
-            Some(Box::new((a..=b).map(move |y| Point { x, y })))
-        } else {
-            let y = q.y;
-            let a = p.x.min(q.x);
-            let b = p.x.max(q.x);
-            Some(Box::new((a..=b).map(move |x| Point { x, y })))
-        }
+fn straight_line(q: Point, p: Point) -> Option<Box<dyn Iterator<Item=Point>>> {
+    if (q.x != p.x) && (q.y != p.y) {
+        return None;
     }
 
-    fn to_relative(self, p: Self) -> Self {
-        Point {
-            x: self.x - p.x,
-            y: self.y - p.y
-        }
-    }
-
-    fn from_relative(self, p: Self) -> Self {
-        Point {
-            x: self.x + p.x,
-            y: self.y + p.y
-        }
-    }
-}
-
-impl<T> Index<Point> for Matrix<T> {
-    type Output = T;
-
-    fn index(&self, Point { x, y }: Point) -> &Self::Output {
-        &self[(x as usize, y as usize)]
-    }
-}
-
-impl<T> IndexMut<Point> for Matrix<T> {
-    fn index_mut(&mut self, Point { x, y }: Point) -> &mut Self::Output {
-        &mut self[(x as usize, y as usize)]
+    if q.x == p.x {
+        let x = q.x;
+        let a = p.y.min(q.y);
+        let b = p.y.max(q.y);
+        Some(Box::new((a..=b).map(move |y| Point { x, y })))
+    } else {
+        let y = q.y;
+        let a = p.x.min(q.x);
+        let b = p.x.max(q.x);
+        Some(Box::new((a..=b).map(move |x| Point { x, y })))
     }
 }
 
@@ -92,88 +51,117 @@ impl Element {
     }
 }
 
-fn main() {
+/* Let one unit of sand fall from the source, returning where it comes to
+ * rest. None if the source is blocked or the sand leaves the grid. */
+fn drop_sand(g: &Matrix<Element>, source: Point) -> Option<Point> {
+    if g[source].is_rigid() {
+        return None;
+    }
+
+    let mut s = source;
+    'falling: loop {
+        /* straight down, then down-left, then down-right */
+        for dx in [0, -1, 1] {
+            let next = s + Point { x: dx, y: 1 };
+            match next.to_index().and_then(|idx| g.get(idx)) {
+                None => return None,
+                Some(e) if !e.is_rigid() => {
+                    s = next;
+                    continue 'falling;
+                },
+                Some(_) => {}
+            }
+        }
+        return Some(s);
+    }
+}
+
+fn fill_with_sand(g: &mut Matrix<Element>, source: Point) -> usize {
+    let mut settled = 0;
+    while let Some(s) = drop_sand(g, source) {
+        g[s] = Element::Sand;
+        settled += 1;
+    }
+    settled
+}
+
+fn main() -> Result<(), ParseError> {
 
     let args: Vec<String> = env::args().collect();
-    let f = fs::File::open(&args[1]).unwrap();
-    let mut r = BufReader::new(f);
 
-    let mut line = String::new();
+    if args.len() != 2 {
+        panic!("Incorrect number of args!")
+    }
+
+    let mut r = open_input(&args[1])?;
+
     let mut points = vec![];
-    while let Ok(_) = r.read_line(&mut line) {
+    while let Some(line) = parse_non_empty_line(&mut r)? {
 
         let mut pairs = vec![];
         for e in line.split("->") {
-            let cap = POINT_REGEX.captures(e.trim()).unwrap();
-            pairs.push(Point { x: i64::from_str_radix(cap.get(1).unwrap().as_str(), 10).unwrap(),
-                               y: i64::from_str_radix(cap.get(2).unwrap().as_str(), 10).unwrap()} );
+            let (x, y) = parse_captures_within(&POINT_REGEX, r.line(), &line, e.trim())?;
+            pairs.push(Point { x, y });
         }
 
         for i in 0 .. pairs.len() - 1 {
-            points.extend(pairs[i].straight_line(&pairs[i+1])
+            points.extend(straight_line(pairs[i], pairs[i+1])
                                   .unwrap());
         }
 
     }
 
-    /* Get the maximum, minimum of all coordinates */
+    let source = Point { x: 500, y: 0 };
+
+    /* Get the maximum, minimum of all coordinates, including the source */
     let x_range = (
-        points.iter().map(|p| p.x).min().unwrap(),
-        points.iter().map(|p| p.x).max().unwrap()
+        points.iter().map(|p| p.x).chain([source.x]).min().unwrap(),
+        points.iter().map(|p| p.x).chain([source.x]).max().unwrap()
     );
 
     let y_range = (
-        points.iter().map(|p| p.y).min().unwrap(),
-        points.iter().map(|p| p.y).max().unwrap()
+        points.iter().map(|p| p.y).chain([source.y]).min().unwrap(),
+        points.iter().map(|p| p.y).chain([source.y]).max().unwrap()
     );
 
+    /* y grows downwards, so rows are y and columns are x */
     let (m, n) = (
-        x_range.1 - x_range.0 + 1,
-        y_range.1 - y_range.0 + 1
+        y_range.1 - y_range.0 + 1,
+        x_range.1 - x_range.0 + 1
     );
 
-    let r = Point { x: x_range.0, y: y_range.1 };
-    let mut g: Matrix<Element> = Matrix::new(m as usize, n as usize);
-    g.fill(Element::Air);
-
-    {
-        let points: Vec<Point> = points.iter().map(|p| p.to_relative(r)).collect();
-        for p in points {
-            g[p] = Element::Rock;
-        }
+    let origin = Point { x: x_range.0, y: y_range.0 };
+    let mut g = Matrix::new_filled(m as usize, n as usize, Element::Air);
+    for &p in points.iter() {
+        g[p - origin] = Element::Rock;
     }
+    let rocks = g.clone();
 
     /*
-     * Base problem:
+     * Base problem: sand leaving the rock bounding box falls forever.
      */
 
-    loop {
-        let mut s = Point { x: 500, y: 0 }.to_relative(r);
-        loop {
-            if g[s].is_rigid() {
-                panic!("Invalid start position for sand.");
-            }
+    let settled = fill_with_sand(&mut g, source - origin);
+    println!("Part one: units of sand at rest: {settled}");
 
-            /* figure out where to go */
-            let n = Point { x: s.x, y: s.y - 1 };
-            if !g[n].is_rigid() {
-                s = n;
-                continue;
-            }
+    /*
+     * Floor two below the lowest rock. Sand piles up in a triangle under
+     * the source, so never spreads further than the floor depth sideways.
+     */
 
-            let l = Point { x: s.x - 1, y: s.y - 1 };
-            let r = Point { x: s.x + 1, y: s.y - 1 };
-            if !g[l].is_rigid() {
-                s = l;
-                continue;
-            } else if !g[r].is_rigid() {
-                s = r;
-                continue;
-            }
+    let floor = y_range.1 + 2;
+    let left = (x_range.0 - (source.x - floor)).max(0);
+    let right = ((source.x + floor) - x_range.1).max(0);
 
-            break;
-        }
-        g[s] = Element::Sand;
+    let mut g = rocks.pad(0, 2, left as usize, right as usize, Element::Air);
+    let (m, n) = g.get_dims();
+    for j in 0 .. n {
+        g[(m-1, j)] = Element::Rock;
     }
-    
+
+    let origin = origin - Point { x: left, y: 0 };
+    let settled = fill_with_sand(&mut g, source - origin);
+    println!("Part two: units of sand at rest: {settled}");
+
+    Ok(())
 }
@@ -1,28 +1,103 @@
+use std::env;
+
 use lazy_static::lazy_static;
 
-use aoc::parser::parse_non_empty_line;
+use aoc::geom::Point2;
+use aoc::parser::{line_format, open_input, parse_non_empty_line, LineFormat, ParseError};
+
+/* The row asked about in part one, and the bound on the distress beacon's
+ * coordinates in part two */
+const QUERY_ROW: i64 = 2_000_000;
+const SEARCH_MAX: i64 = 4_000_000;
 
 struct Sensor {
-    pos: (usize, usize),
-    beacon_pos: (usize, usize)
+    pos: Point2<i64>,
+    beacon_pos: Point2<i64>
+}
+
+impl Sensor {
+    fn radius(&self) -> i64 {
+        (self.beacon_pos - self.pos).manhattan()
+    }
+
+    /* Inclusive range of x this sensor covers in row y */
+    fn coverage(&self, y: i64) -> Option<(i64, i64)> {
+        let reach = self.radius() - (y - self.pos.y).abs();
+        (reach >= 0).then_some((self.pos.x - reach, self.pos.x + reach))
+    }
 }
 
 lazy_static! {
-    static ref SENSOR_REGEX: Regex = r"Sensor at x=([0-9]+), y=([0-9]+): closest beacon is at x=([0-9]+), y=([0-9]+)".unwrap();
+    static ref SENSOR_FORMAT: LineFormat
+         = line_format("Sensor at x={sx}, y={sy}: closest beacon is at x={bx}, y={by}");
 }
 
-fn parse_sensor_data(line: &str) -> Sensor {
-    
+fn parse_sensor_data(line_no: usize, line: &str) -> Result<Sensor, ParseError> {
+    let (sx, sy, bx, by) = SENSOR_FORMAT.parse(line_no, line)?;
+    Ok(Sensor { pos: Point2::new(sx, sy), beacon_pos: Point2::new(bx, by) })
+}
+
+/* The sensors' coverage of row y as sorted, disjoint inclusive ranges */
+fn row_coverage(sensors: &[Sensor], y: i64) -> Vec<(i64, i64)> {
+    let mut ranges: Vec<_> = sensors.iter().filter_map(|s| s.coverage(y)).collect();
+    ranges.sort();
+
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (a, b) in ranges {
+        match merged.last_mut() {
+            Some((_, end)) if a <= *end + 1 => *end = (*end).max(b),
+            _ => merged.push((a, b)),
+        }
+    }
+    merged
 }
 
-fn main() {
+fn main() -> Result<(), ParseError> {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 {
+        panic!("Incorrect number of args!")
+    }
+
+    let mut r = open_input(&args[1])?;
+
+    let mut sensors = vec![];
+    while let Some(line) = parse_non_empty_line(&mut r)? {
+        sensors.push(parse_sensor_data(r.line(), &line)?);
+    }
+
+    /* Part One: covered cells in the row, less the beacons already there */
+    let covered = row_coverage(&sensors, QUERY_ROW).iter()
+                                                   .map(|(a, b)| b - a + 1)
+                                                   .sum::<i64>();
+    let mut beacons: Vec<_> = sensors.iter()
+                                     .map(|s| s.beacon_pos)
+                                     .filter(|b| b.y == QUERY_ROW)
+                                     .map(|b| b.x)
+                                     .collect();
+    beacons.sort();
+    beacons.dedup();
+
+    println!("Positions in row {} that cannot hold a beacon: {}",
+             QUERY_ROW, covered - beacons.len() as i64);
 
-    let mut r = {
-        let f = fs::File::open(&args[1])?;
-        BufReader::new(f)
-    };
-    
-    while let Some(line) = parse_non_empty_line(&mut r) {
-        
+    /* Part Two: the only row with a gap inside the search area */
+    let distress = (0..=SEARCH_MAX).find_map(|y| {
+        let mut x = 0;
+        for (a, b) in row_coverage(&sensors, y) {
+            if a > x {
+                break;
+            }
+            x = x.max(b + 1);
+        }
+        (x <= SEARCH_MAX).then_some(Point2::new(x, y))
+    });
+
+    match distress {
+        Some(p) => println!("The distress beacon's tuning frequency is {}",
+                            p.x * 4_000_000 + p.y),
+        None => println!("No position is left for the distress beacon"),
     }
+
+    Ok(())
 }